@@ -1,32 +1,1749 @@
 // Generated by `wit-bindgen` 0.41.0. DO NOT EDIT!
 // Options used:
 //   * runtime_path: "wit_bindgen_rt"
+pub type UpstreamResponse = rilot::proxy::types::UpstreamResponse;
+pub type ResponseOverride = rilot::proxy::types::ResponseOverride;
+pub type Request = rilot::proxy::types::Request;
+pub type RequestOverride = rilot::proxy::types::RequestOverride;
 #[doc(hidden)]
 #[allow(non_snake_case)]
-pub unsafe fn _export_modify_request_cabi<T: Guest>() {
+pub unsafe fn _export_modify_response_cabi<T: Guest>(
+    arg0: i32,
+    arg1: *mut u8,
+    arg2: usize,
+    arg3: i32,
+    arg4: *mut u8,
+    arg5: usize,
+    arg6: *mut u8,
+    arg7: usize,
+    arg8: i32,
+    arg9: *mut u8,
+    arg10: usize,
+) -> *mut u8 {
     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
-    T::modify_request();
+    let base6 = arg1;
+    let len6 = arg2;
+    let mut result6 = _rt::Vec::with_capacity(len6);
+    for i in 0..len6 {
+        let base = base6.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        let e6 = {
+            let l0 = *base.add(0).cast::<*mut u8>();
+            let l1 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len2 = l1;
+            let bytes2 = _rt::Vec::from_raw_parts(l0.cast(), len2, len2);
+            let l3 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l4 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len5 = l4;
+            let bytes5 = _rt::Vec::from_raw_parts(l3.cast(), len5, len5);
+            (_rt::string_lift(bytes2), _rt::string_lift(bytes5))
+        };
+        result6.push(e6);
+    }
+    _rt::cabi_dealloc(
+        base6,
+        len6 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let base14 = arg6;
+    let len14 = arg7;
+    let mut result14 = _rt::Vec::with_capacity(len14);
+    for i in 0..len14 {
+        let base = base14.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        let e14 = {
+            let l8 = *base.add(0).cast::<*mut u8>();
+            let l9 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len10 = l9;
+            let bytes10 = _rt::Vec::from_raw_parts(l8.cast(), len10, len10);
+            let l11 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l12 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len13 = l12;
+            let bytes13 = _rt::Vec::from_raw_parts(l11.cast(), len13, len13);
+            (_rt::string_lift(bytes10), _rt::string_lift(bytes13))
+        };
+        result14.push(e14);
+    }
+    _rt::cabi_dealloc(
+        base14,
+        len14 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result16 = T::modify_response(rilot::proxy::types::UpstreamResponse {
+        status: arg0 as u16,
+        headers: result6,
+        body: match arg3 {
+            0 => None,
+            1 => {
+                let e = {
+                    let len7 = arg5;
+                    _rt::Vec::from_raw_parts(arg4.cast(), len7, len7)
+                };
+                Some(e)
+            }
+            _ => _rt::invalid_enum_discriminant(),
+        },
+        context: result14,
+        config: match arg8 {
+            0 => None,
+            1 => {
+                let e = {
+                    let len15 = arg10;
+                    let bytes15 = _rt::Vec::from_raw_parts(arg9.cast(), len15, len15);
+                    _rt::string_lift(bytes15)
+                };
+                Some(e)
+            }
+            _ => _rt::invalid_enum_discriminant(),
+        },
+    });
+    let ptr17 = (&raw mut _RET_AREA.0).cast::<u8>();
+    let rilot::proxy::types::ResponseOverride {
+        status: status18,
+        headers_to_update: headers_to_update18,
+        headers_to_remove: headers_to_remove18,
+        body: body18,
+        stop: stop18,
+    } = result16;
+    match status18 {
+        Some(e) => {
+            *ptr17.add(0).cast::<u8>() = (1i32) as u8;
+            *ptr17.add(2).cast::<u16>() = (_rt::as_i32(e)) as u16;
+        }
+        None => {
+            *ptr17.add(0).cast::<u8>() = (0i32) as u8;
+        }
+    };
+    let vec22 = headers_to_update18;
+    let len22 = vec22.len();
+    let layout22 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec22.len() * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result22 = if layout22.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout22).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout22);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec22.into_iter().enumerate() {
+        let base = result22.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let (t19_0, t19_1) = e;
+            let vec20 = (t19_0.into_bytes()).into_boxed_slice();
+            let ptr20 = vec20.as_ptr().cast::<u8>();
+            let len20 = vec20.len();
+            ::core::mem::forget(vec20);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len20;
+            *base.add(0).cast::<*mut u8>() = ptr20.cast_mut();
+            let vec21 = (t19_1.into_bytes()).into_boxed_slice();
+            let ptr21 = vec21.as_ptr().cast::<u8>();
+            let len21 = vec21.len();
+            ::core::mem::forget(vec21);
+            *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len21;
+            *base.add(2 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr21
+                .cast_mut();
+        }
+    }
+    *ptr17.add(2 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len22;
+    *ptr17.add(::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result22;
+    let vec24 = headers_to_remove18;
+    let len24 = vec24.len();
+    let layout24 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec24.len() * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result24 = if layout24.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout24).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout24);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec24.into_iter().enumerate() {
+        let base = result24.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let vec23 = (e.into_bytes()).into_boxed_slice();
+            let ptr23 = vec23.as_ptr().cast::<u8>();
+            let len23 = vec23.len();
+            ::core::mem::forget(vec23);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len23;
+            *base.add(0).cast::<*mut u8>() = ptr23.cast_mut();
+        }
+    }
+    *ptr17.add(4 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len24;
+    *ptr17.add(3 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result24;
+    match body18 {
+        Some(e) => {
+            *ptr17.add(5 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32)
+                as u8;
+            let vec25 = (e).into_boxed_slice();
+            let ptr25 = vec25.as_ptr().cast::<u8>();
+            let len25 = vec25.len();
+            ::core::mem::forget(vec25);
+            *ptr17.add(7 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len25;
+            *ptr17.add(6 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr25
+                .cast_mut();
+        }
+        None => {
+            *ptr17.add(5 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32)
+                as u8;
+        }
+    };
+    *ptr17.add(8 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (match stop18 {
+        true => 1,
+        false => 0,
+    }) as u8;
+    ptr17
+}
+#[doc(hidden)]
+#[allow(non_snake_case)]
+pub unsafe fn __post_return_modify_response<T: Guest>(arg0: *mut u8) {
+    let l0 = *arg0.add(::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l1 = *arg0.add(2 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base6 = l0;
+    let len6 = l1;
+    for i in 0..len6 {
+        let base = base6.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l2 = *base.add(0).cast::<*mut u8>();
+            let l3 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l2, l3, 1);
+            let l4 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l5 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l4, l5, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base6,
+        len6 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l7 = *arg0.add(3 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l8 = *arg0.add(4 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base11 = l7;
+    let len11 = l8;
+    for i in 0..len11 {
+        let base = base11.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l9 = *base.add(0).cast::<*mut u8>();
+            let l10 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l9, l10, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base11,
+        len11 * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l12 = i32::from(*arg0.add(5 * ::core::mem::size_of::<*const u8>()).cast::<u8>());
+    match l12 {
+        0 => {}
+        _ => {
+            let l13 = *arg0
+                .add(6 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l14 = *arg0.add(7 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let base15 = l13;
+            let len15 = l14;
+            _rt::cabi_dealloc(base15, len15 * 1, 1);
+        }
+    }
+}
+#[doc(hidden)]
+#[allow(non_snake_case)]
+pub unsafe fn _export_modify_request_cabi<T: Guest>(arg0: *mut u8) -> *mut u8 {
+    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+    let l0 = *arg0.add(0).cast::<*mut u8>();
+    let l1 = *arg0.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let len2 = l1;
+    let bytes2 = _rt::Vec::from_raw_parts(l0.cast(), len2, len2);
+    let l3 = *arg0.add(2 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l4 = *arg0.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let len5 = l4;
+    let bytes5 = _rt::Vec::from_raw_parts(l3.cast(), len5, len5);
+    let l6 = *arg0.add(4 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l7 = *arg0.add(5 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base14 = l6;
+    let len14 = l7;
+    let mut result14 = _rt::Vec::with_capacity(len14);
+    for i in 0..len14 {
+        let base = base14.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        let e14 = {
+            let l8 = *base.add(0).cast::<*mut u8>();
+            let l9 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len10 = l9;
+            let bytes10 = _rt::Vec::from_raw_parts(l8.cast(), len10, len10);
+            let l11 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l12 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len13 = l12;
+            let bytes13 = _rt::Vec::from_raw_parts(l11.cast(), len13, len13);
+            (_rt::string_lift(bytes10), _rt::string_lift(bytes13))
+        };
+        result14.push(e14);
+    }
+    _rt::cabi_dealloc(
+        base14,
+        len14 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l15 = *arg0.add(6 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l16 = *arg0.add(7 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base23 = l15;
+    let len23 = l16;
+    let mut result23 = _rt::Vec::with_capacity(len23);
+    for i in 0..len23 {
+        let base = base23.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        let e23 = {
+            let l17 = *base.add(0).cast::<*mut u8>();
+            let l18 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len19 = l18;
+            let bytes19 = _rt::Vec::from_raw_parts(l17.cast(), len19, len19);
+            let l20 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l21 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len22 = l21;
+            let bytes22 = _rt::Vec::from_raw_parts(l20.cast(), len22, len22);
+            (_rt::string_lift(bytes19), _rt::string_lift(bytes22))
+        };
+        result23.push(e23);
+    }
+    _rt::cabi_dealloc(
+        base23,
+        len23 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l24 = *arg0.add(8 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l25 = *arg0.add(9 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let len26 = l25;
+    let l27 = *arg0.add(10 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l28 = *arg0.add(11 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base35 = l27;
+    let len35 = l28;
+    let mut result35 = _rt::Vec::with_capacity(len35);
+    for i in 0..len35 {
+        let base = base35.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        let e35 = {
+            let l29 = *base.add(0).cast::<*mut u8>();
+            let l30 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len31 = l30;
+            let bytes31 = _rt::Vec::from_raw_parts(l29.cast(), len31, len31);
+            let l32 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l33 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len34 = l33;
+            let bytes34 = _rt::Vec::from_raw_parts(l32.cast(), len34, len34);
+            (_rt::string_lift(bytes31), _rt::string_lift(bytes34))
+        };
+        result35.push(e35);
+    }
+    _rt::cabi_dealloc(
+        base35,
+        len35 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l36 = i32::from(
+        *arg0.add(12 * ::core::mem::size_of::<*const u8>()).cast::<u8>(),
+    );
+    let l40 = *arg0.add(15 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l41 = *arg0.add(16 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base48 = l40;
+    let len48 = l41;
+    let mut result48 = _rt::Vec::with_capacity(len48);
+    for i in 0..len48 {
+        let base = base48.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        let e48 = {
+            let l42 = *base.add(0).cast::<*mut u8>();
+            let l43 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len44 = l43;
+            let bytes44 = _rt::Vec::from_raw_parts(l42.cast(), len44, len44);
+            let l45 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l46 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            let len47 = l46;
+            let bytes47 = _rt::Vec::from_raw_parts(l45.cast(), len47, len47);
+            (_rt::string_lift(bytes44), _rt::string_lift(bytes47))
+        };
+        result48.push(e48);
+    }
+    _rt::cabi_dealloc(
+        base48,
+        len48 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result49 = T::modify_request(rilot::proxy::types::Request {
+        method: _rt::string_lift(bytes2),
+        path: _rt::string_lift(bytes5),
+        query: result14,
+        headers: result23,
+        body: _rt::Vec::from_raw_parts(l24.cast(), len26, len26),
+        context: result35,
+        config: match l36 {
+            0 => None,
+            1 => {
+                let e = {
+                    let l37 = *arg0
+                        .add(13 * ::core::mem::size_of::<*const u8>())
+                        .cast::<*mut u8>();
+                    let l38 = *arg0
+                        .add(14 * ::core::mem::size_of::<*const u8>())
+                        .cast::<usize>();
+                    let len39 = l38;
+                    let bytes39 = _rt::Vec::from_raw_parts(l37.cast(), len39, len39);
+                    _rt::string_lift(bytes39)
+                };
+                Some(e)
+            }
+            _ => _rt::invalid_enum_discriminant(),
+        },
+        params: result48,
+    });
+    _rt::cabi_dealloc(
+        arg0,
+        17 * ::core::mem::size_of::<*const u8>(),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let ptr50 = (&raw mut _RET_AREA.0).cast::<u8>();
+    let rilot::proxy::types::RequestOverride {
+        app_url: app_url51,
+        method: method51,
+        path: path51,
+        query_params_to_set: query_params_to_set51,
+        query_params_to_remove: query_params_to_remove51,
+        headers_to_update: headers_to_update51,
+        headers_to_remove: headers_to_remove51,
+        response_headers_to_add: response_headers_to_add51,
+        response_headers_to_remove: response_headers_to_remove51,
+        body: body51,
+        response: response51,
+        context_to_set: context_to_set51,
+        context_to_remove: context_to_remove51,
+        stop: stop51,
+    } = result49;
+    match app_url51 {
+        Some(e) => {
+            *ptr50.add(0).cast::<u8>() = (1i32) as u8;
+            let vec52 = (e.into_bytes()).into_boxed_slice();
+            let ptr52 = vec52.as_ptr().cast::<u8>();
+            let len52 = vec52.len();
+            ::core::mem::forget(vec52);
+            *ptr50.add(2 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len52;
+            *ptr50.add(::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr52
+                .cast_mut();
+        }
+        None => {
+            *ptr50.add(0).cast::<u8>() = (0i32) as u8;
+        }
+    };
+    match method51 {
+        Some(e) => {
+            *ptr50.add(3 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32)
+                as u8;
+            let vec53 = (e.into_bytes()).into_boxed_slice();
+            let ptr53 = vec53.as_ptr().cast::<u8>();
+            let len53 = vec53.len();
+            ::core::mem::forget(vec53);
+            *ptr50.add(5 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len53;
+            *ptr50.add(4 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr53
+                .cast_mut();
+        }
+        None => {
+            *ptr50.add(3 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32)
+                as u8;
+        }
+    };
+    match path51 {
+        Some(e) => {
+            *ptr50.add(6 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32)
+                as u8;
+            let vec54 = (e.into_bytes()).into_boxed_slice();
+            let ptr54 = vec54.as_ptr().cast::<u8>();
+            let len54 = vec54.len();
+            ::core::mem::forget(vec54);
+            *ptr50.add(8 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len54;
+            *ptr50.add(7 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr54
+                .cast_mut();
+        }
+        None => {
+            *ptr50.add(6 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32)
+                as u8;
+        }
+    };
+    let vec58 = query_params_to_set51;
+    let len58 = vec58.len();
+    let layout58 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec58.len() * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result58 = if layout58.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout58).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout58);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec58.into_iter().enumerate() {
+        let base = result58.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let (t55_0, t55_1) = e;
+            let vec56 = (t55_0.into_bytes()).into_boxed_slice();
+            let ptr56 = vec56.as_ptr().cast::<u8>();
+            let len56 = vec56.len();
+            ::core::mem::forget(vec56);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len56;
+            *base.add(0).cast::<*mut u8>() = ptr56.cast_mut();
+            let vec57 = (t55_1.into_bytes()).into_boxed_slice();
+            let ptr57 = vec57.as_ptr().cast::<u8>();
+            let len57 = vec57.len();
+            ::core::mem::forget(vec57);
+            *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len57;
+            *base.add(2 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr57
+                .cast_mut();
+        }
+    }
+    *ptr50.add(10 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len58;
+    *ptr50.add(9 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result58;
+    let vec60 = query_params_to_remove51;
+    let len60 = vec60.len();
+    let layout60 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec60.len() * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result60 = if layout60.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout60).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout60);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec60.into_iter().enumerate() {
+        let base = result60.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let vec59 = (e.into_bytes()).into_boxed_slice();
+            let ptr59 = vec59.as_ptr().cast::<u8>();
+            let len59 = vec59.len();
+            ::core::mem::forget(vec59);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len59;
+            *base.add(0).cast::<*mut u8>() = ptr59.cast_mut();
+        }
+    }
+    *ptr50.add(12 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len60;
+    *ptr50.add(11 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result60;
+    let vec64 = headers_to_update51;
+    let len64 = vec64.len();
+    let layout64 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec64.len() * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result64 = if layout64.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout64).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout64);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec64.into_iter().enumerate() {
+        let base = result64.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let (t61_0, t61_1) = e;
+            let vec62 = (t61_0.into_bytes()).into_boxed_slice();
+            let ptr62 = vec62.as_ptr().cast::<u8>();
+            let len62 = vec62.len();
+            ::core::mem::forget(vec62);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len62;
+            *base.add(0).cast::<*mut u8>() = ptr62.cast_mut();
+            let vec63 = (t61_1.into_bytes()).into_boxed_slice();
+            let ptr63 = vec63.as_ptr().cast::<u8>();
+            let len63 = vec63.len();
+            ::core::mem::forget(vec63);
+            *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len63;
+            *base.add(2 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr63
+                .cast_mut();
+        }
+    }
+    *ptr50.add(14 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len64;
+    *ptr50.add(13 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result64;
+    let vec66 = headers_to_remove51;
+    let len66 = vec66.len();
+    let layout66 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec66.len() * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result66 = if layout66.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout66).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout66);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec66.into_iter().enumerate() {
+        let base = result66.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let vec65 = (e.into_bytes()).into_boxed_slice();
+            let ptr65 = vec65.as_ptr().cast::<u8>();
+            let len65 = vec65.len();
+            ::core::mem::forget(vec65);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len65;
+            *base.add(0).cast::<*mut u8>() = ptr65.cast_mut();
+        }
+    }
+    *ptr50.add(16 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len66;
+    *ptr50.add(15 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result66;
+    let vec70 = response_headers_to_add51;
+    let len70 = vec70.len();
+    let layout70 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec70.len() * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result70 = if layout70.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout70).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout70);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec70.into_iter().enumerate() {
+        let base = result70.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let (t67_0, t67_1) = e;
+            let vec68 = (t67_0.into_bytes()).into_boxed_slice();
+            let ptr68 = vec68.as_ptr().cast::<u8>();
+            let len68 = vec68.len();
+            ::core::mem::forget(vec68);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len68;
+            *base.add(0).cast::<*mut u8>() = ptr68.cast_mut();
+            let vec69 = (t67_1.into_bytes()).into_boxed_slice();
+            let ptr69 = vec69.as_ptr().cast::<u8>();
+            let len69 = vec69.len();
+            ::core::mem::forget(vec69);
+            *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len69;
+            *base.add(2 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr69
+                .cast_mut();
+        }
+    }
+    *ptr50.add(18 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len70;
+    *ptr50.add(17 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result70;
+    let vec72 = response_headers_to_remove51;
+    let len72 = vec72.len();
+    let layout72 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec72.len() * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result72 = if layout72.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout72).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout72);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec72.into_iter().enumerate() {
+        let base = result72.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let vec71 = (e.into_bytes()).into_boxed_slice();
+            let ptr71 = vec71.as_ptr().cast::<u8>();
+            let len71 = vec71.len();
+            ::core::mem::forget(vec71);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len71;
+            *base.add(0).cast::<*mut u8>() = ptr71.cast_mut();
+        }
+    }
+    *ptr50.add(20 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len72;
+    *ptr50.add(19 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result72;
+    match body51 {
+        Some(e) => {
+            *ptr50.add(21 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32)
+                as u8;
+            let vec73 = (e).into_boxed_slice();
+            let ptr73 = vec73.as_ptr().cast::<u8>();
+            let len73 = vec73.len();
+            ::core::mem::forget(vec73);
+            *ptr50.add(23 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len73;
+            *ptr50.add(22 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr73
+                .cast_mut();
+        }
+        None => {
+            *ptr50.add(21 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32)
+                as u8;
+        }
+    };
+    match response51 {
+        Some(e) => {
+            *ptr50.add(24 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32)
+                as u8;
+            let rilot::proxy::types::Response {
+                status: status74,
+                headers: headers74,
+                body: body74,
+            } = e;
+            *ptr50.add(25 * ::core::mem::size_of::<*const u8>()).cast::<u16>() = (_rt::as_i32(
+                status74,
+            )) as u16;
+            let vec78 = headers74;
+            let len78 = vec78.len();
+            let layout78 = _rt::alloc::Layout::from_size_align_unchecked(
+                vec78.len() * (4 * ::core::mem::size_of::<*const u8>()),
+                ::core::mem::size_of::<*const u8>(),
+            );
+            let result78 = if layout78.size() != 0 {
+                let ptr = _rt::alloc::alloc(layout78).cast::<u8>();
+                if ptr.is_null() {
+                    _rt::alloc::handle_alloc_error(layout78);
+                }
+                ptr
+            } else {
+                ::core::ptr::null_mut()
+            };
+            for (i, e) in vec78.into_iter().enumerate() {
+                let base = result78.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                {
+                    let (t75_0, t75_1) = e;
+                    let vec76 = (t75_0.into_bytes()).into_boxed_slice();
+                    let ptr76 = vec76.as_ptr().cast::<u8>();
+                    let len76 = vec76.len();
+                    ::core::mem::forget(vec76);
+                    *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len76;
+                    *base.add(0).cast::<*mut u8>() = ptr76.cast_mut();
+                    let vec77 = (t75_1.into_bytes()).into_boxed_slice();
+                    let ptr77 = vec77.as_ptr().cast::<u8>();
+                    let len77 = vec77.len();
+                    ::core::mem::forget(vec77);
+                    *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len77;
+                    *base
+                        .add(2 * ::core::mem::size_of::<*const u8>())
+                        .cast::<*mut u8>() = ptr77.cast_mut();
+                }
+            }
+            *ptr50.add(27 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len78;
+            *ptr50.add(26 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result78;
+            let vec79 = (body74).into_boxed_slice();
+            let ptr79 = vec79.as_ptr().cast::<u8>();
+            let len79 = vec79.len();
+            ::core::mem::forget(vec79);
+            *ptr50.add(29 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len79;
+            *ptr50.add(28 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr79
+                .cast_mut();
+        }
+        None => {
+            *ptr50.add(24 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32)
+                as u8;
+        }
+    };
+    let vec83 = context_to_set51;
+    let len83 = vec83.len();
+    let layout83 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec83.len() * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result83 = if layout83.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout83).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout83);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec83.into_iter().enumerate() {
+        let base = result83.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let (t80_0, t80_1) = e;
+            let vec81 = (t80_0.into_bytes()).into_boxed_slice();
+            let ptr81 = vec81.as_ptr().cast::<u8>();
+            let len81 = vec81.len();
+            ::core::mem::forget(vec81);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len81;
+            *base.add(0).cast::<*mut u8>() = ptr81.cast_mut();
+            let vec82 = (t80_1.into_bytes()).into_boxed_slice();
+            let ptr82 = vec82.as_ptr().cast::<u8>();
+            let len82 = vec82.len();
+            ::core::mem::forget(vec82);
+            *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len82;
+            *base.add(2 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr82
+                .cast_mut();
+        }
+    }
+    *ptr50.add(31 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len83;
+    *ptr50.add(30 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result83;
+    let vec85 = context_to_remove51;
+    let len85 = vec85.len();
+    let layout85 = _rt::alloc::Layout::from_size_align_unchecked(
+        vec85.len() * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let result85 = if layout85.size() != 0 {
+        let ptr = _rt::alloc::alloc(layout85).cast::<u8>();
+        if ptr.is_null() {
+            _rt::alloc::handle_alloc_error(layout85);
+        }
+        ptr
+    } else {
+        ::core::ptr::null_mut()
+    };
+    for (i, e) in vec85.into_iter().enumerate() {
+        let base = result85.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let vec84 = (e.into_bytes()).into_boxed_slice();
+            let ptr84 = vec84.as_ptr().cast::<u8>();
+            let len84 = vec84.len();
+            ::core::mem::forget(vec84);
+            *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len84;
+            *base.add(0).cast::<*mut u8>() = ptr84.cast_mut();
+        }
+    }
+    *ptr50.add(33 * ::core::mem::size_of::<*const u8>()).cast::<usize>() = len85;
+    *ptr50.add(32 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = result85;
+    *ptr50.add(34 * ::core::mem::size_of::<*const u8>()).cast::<u8>() = (match stop51 {
+        true => 1,
+        false => 0,
+    }) as u8;
+    ptr50
+}
+#[doc(hidden)]
+#[allow(non_snake_case)]
+pub unsafe fn __post_return_modify_request<T: Guest>(arg0: *mut u8) {
+    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+    match l0 {
+        0 => {}
+        _ => {
+            let l1 = *arg0.add(::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+            let l2 = *arg0.add(2 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l1, l2, 1);
+        }
+    }
+    let l3 = i32::from(*arg0.add(3 * ::core::mem::size_of::<*const u8>()).cast::<u8>());
+    match l3 {
+        0 => {}
+        _ => {
+            let l4 = *arg0
+                .add(4 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l5 = *arg0.add(5 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l4, l5, 1);
+        }
+    }
+    let l6 = i32::from(*arg0.add(6 * ::core::mem::size_of::<*const u8>()).cast::<u8>());
+    match l6 {
+        0 => {}
+        _ => {
+            let l7 = *arg0
+                .add(7 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l8 = *arg0.add(8 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l7, l8, 1);
+        }
+    }
+    let l9 = *arg0.add(9 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l10 = *arg0.add(10 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base15 = l9;
+    let len15 = l10;
+    for i in 0..len15 {
+        let base = base15.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l11 = *base.add(0).cast::<*mut u8>();
+            let l12 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l11, l12, 1);
+            let l13 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l14 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l13, l14, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base15,
+        len15 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l16 = *arg0.add(11 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l17 = *arg0.add(12 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base20 = l16;
+    let len20 = l17;
+    for i in 0..len20 {
+        let base = base20.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l18 = *base.add(0).cast::<*mut u8>();
+            let l19 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l18, l19, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base20,
+        len20 * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l21 = *arg0.add(13 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l22 = *arg0.add(14 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base27 = l21;
+    let len27 = l22;
+    for i in 0..len27 {
+        let base = base27.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l23 = *base.add(0).cast::<*mut u8>();
+            let l24 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l23, l24, 1);
+            let l25 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l26 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l25, l26, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base27,
+        len27 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l28 = *arg0.add(15 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l29 = *arg0.add(16 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base32 = l28;
+    let len32 = l29;
+    for i in 0..len32 {
+        let base = base32.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l30 = *base.add(0).cast::<*mut u8>();
+            let l31 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l30, l31, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base32,
+        len32 * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l33 = *arg0.add(17 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l34 = *arg0.add(18 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base39 = l33;
+    let len39 = l34;
+    for i in 0..len39 {
+        let base = base39.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l35 = *base.add(0).cast::<*mut u8>();
+            let l36 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l35, l36, 1);
+            let l37 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l38 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l37, l38, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base39,
+        len39 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l40 = *arg0.add(19 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l41 = *arg0.add(20 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base44 = l40;
+    let len44 = l41;
+    for i in 0..len44 {
+        let base = base44.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l42 = *base.add(0).cast::<*mut u8>();
+            let l43 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l42, l43, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base44,
+        len44 * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l45 = i32::from(
+        *arg0.add(21 * ::core::mem::size_of::<*const u8>()).cast::<u8>(),
+    );
+    match l45 {
+        0 => {}
+        _ => {
+            let l46 = *arg0
+                .add(22 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l47 = *arg0
+                .add(23 * ::core::mem::size_of::<*const u8>())
+                .cast::<usize>();
+            let base48 = l46;
+            let len48 = l47;
+            _rt::cabi_dealloc(base48, len48 * 1, 1);
+        }
+    }
+    let l49 = i32::from(
+        *arg0.add(24 * ::core::mem::size_of::<*const u8>()).cast::<u8>(),
+    );
+    match l49 {
+        0 => {}
+        _ => {
+            let l50 = *arg0
+                .add(26 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l51 = *arg0
+                .add(27 * ::core::mem::size_of::<*const u8>())
+                .cast::<usize>();
+            let base56 = l50;
+            let len56 = l51;
+            for i in 0..len56 {
+                let base = base56.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                {
+                    let l52 = *base.add(0).cast::<*mut u8>();
+                    let l53 = *base
+                        .add(::core::mem::size_of::<*const u8>())
+                        .cast::<usize>();
+                    _rt::cabi_dealloc(l52, l53, 1);
+                    let l54 = *base
+                        .add(2 * ::core::mem::size_of::<*const u8>())
+                        .cast::<*mut u8>();
+                    let l55 = *base
+                        .add(3 * ::core::mem::size_of::<*const u8>())
+                        .cast::<usize>();
+                    _rt::cabi_dealloc(l54, l55, 1);
+                }
+            }
+            _rt::cabi_dealloc(
+                base56,
+                len56 * (4 * ::core::mem::size_of::<*const u8>()),
+                ::core::mem::size_of::<*const u8>(),
+            );
+            let l57 = *arg0
+                .add(28 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l58 = *arg0
+                .add(29 * ::core::mem::size_of::<*const u8>())
+                .cast::<usize>();
+            let base59 = l57;
+            let len59 = l58;
+            _rt::cabi_dealloc(base59, len59 * 1, 1);
+        }
+    }
+    let l60 = *arg0.add(30 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l61 = *arg0.add(31 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base66 = l60;
+    let len66 = l61;
+    for i in 0..len66 {
+        let base = base66.add(i * (4 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l62 = *base.add(0).cast::<*mut u8>();
+            let l63 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l62, l63, 1);
+            let l64 = *base
+                .add(2 * ::core::mem::size_of::<*const u8>())
+                .cast::<*mut u8>();
+            let l65 = *base.add(3 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l64, l65, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base66,
+        len66 * (4 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
+    let l67 = *arg0.add(32 * ::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+    let l68 = *arg0.add(33 * ::core::mem::size_of::<*const u8>()).cast::<usize>();
+    let base71 = l67;
+    let len71 = l68;
+    for i in 0..len71 {
+        let base = base71.add(i * (2 * ::core::mem::size_of::<*const u8>()));
+        {
+            let l69 = *base.add(0).cast::<*mut u8>();
+            let l70 = *base.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+            _rt::cabi_dealloc(l69, l70, 1);
+        }
+    }
+    _rt::cabi_dealloc(
+        base71,
+        len71 * (2 * ::core::mem::size_of::<*const u8>()),
+        ::core::mem::size_of::<*const u8>(),
+    );
 }
 pub trait Guest {
-    fn modify_request() -> ();
+    fn modify_response(res: UpstreamResponse) -> ResponseOverride;
+    fn modify_request(req: Request) -> RequestOverride;
 }
 #[doc(hidden)]
 macro_rules! __export_world_rilot_override_cabi {
     ($ty:ident with_types_in $($path_to_types:tt)*) => {
-        const _ : () = { #[unsafe (export_name = "modify-request")] unsafe extern "C" fn
-        export_modify_request() { unsafe { $($path_to_types)*::
-        _export_modify_request_cabi::<$ty > () } } };
+        const _ : () = { #[unsafe (export_name = "modify-response")] unsafe extern "C" fn
+        export_modify_response(arg0 : i32, arg1 : * mut u8, arg2 : usize, arg3 : i32,
+        arg4 : * mut u8, arg5 : usize, arg6 : * mut u8, arg7 : usize, arg8 : i32, arg9 :
+        * mut u8, arg10 : usize,) -> * mut u8 { unsafe { $($path_to_types)*::
+        _export_modify_response_cabi::<$ty > (arg0, arg1, arg2, arg3, arg4, arg5, arg6,
+        arg7, arg8, arg9, arg10) } } #[unsafe (export_name =
+        "cabi_post_modify-response")] unsafe extern "C" fn
+        _post_return_modify_response(arg0 : * mut u8,) { unsafe { $($path_to_types)*::
+        __post_return_modify_response::<$ty > (arg0) } } #[unsafe (export_name =
+        "modify-request")] unsafe extern "C" fn export_modify_request(arg0 : * mut u8,)
+        -> * mut u8 { unsafe { $($path_to_types)*:: _export_modify_request_cabi::<$ty >
+        (arg0) } } #[unsafe (export_name = "cabi_post_modify-request")] unsafe extern "C"
+        fn _post_return_modify_request(arg0 : * mut u8,) { unsafe { $($path_to_types)*::
+        __post_return_modify_request::<$ty > (arg0) } } };
     };
 }
 #[doc(hidden)]
 pub(crate) use __export_world_rilot_override_cabi;
+#[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+#[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+struct _RetArea(
+    [::core::mem::MaybeUninit<u8>; 35 * ::core::mem::size_of::<*const u8>()],
+);
+static mut _RET_AREA: _RetArea = _RetArea(
+    [::core::mem::MaybeUninit::uninit(); 35 * ::core::mem::size_of::<*const u8>()],
+);
+#[rustfmt::skip]
+#[allow(dead_code, clippy::all)]
+pub mod rilot {
+    pub mod proxy {
+        /// Records exchanged between rilot and override components.
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod types {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            /// Header name/value pairs. Names may repeat, e.g. one `set-cookie` entry
+            /// per cookie. Entries in a `*-to-update` or `*-to-add` list replace every
+            /// existing value of their name.
+            pub type Headers = _rt::Vec<(_rt::String, _rt::String)>;
+            /// Per-request key/value map shared by every override in a route's chain.
+            pub type Context = _rt::Vec<(_rt::String, _rt::String)>;
+            /// The downstream request as seen by `modify-request`.
+            #[derive(Clone)]
+            pub struct Request {
+                pub method: _rt::String,
+                pub path: _rt::String,
+                /// Decoded query string parameters, in order.
+                pub query: _rt::Vec<(_rt::String, _rt::String)>,
+                pub headers: Headers,
+                pub body: _rt::Vec<u8>,
+                /// Values written by earlier overrides in the chain.
+                pub context: Context,
+                /// The route's `override_config` as JSON text.
+                pub config: Option<_rt::String>,
+                /// Named capture groups of the matched `regex` rule, or `{name}`
+                /// segments of a `glob` rule; empty before routing and for other rule
+                /// types.
+                pub params: _rt::Vec<(_rt::String, _rt::String)>,
+            }
+            impl ::core::fmt::Debug for Request {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("Request")
+                        .field("method", &self.method)
+                        .field("path", &self.path)
+                        .field("query", &self.query)
+                        .field("headers", &self.headers)
+                        .field("body", &self.body)
+                        .field("context", &self.context)
+                        .field("config", &self.config)
+                        .field("params", &self.params)
+                        .finish()
+                }
+            }
+            /// A complete response produced by an override.
+            #[derive(Clone)]
+            pub struct Response {
+                pub status: u16,
+                pub headers: Headers,
+                pub body: _rt::Vec<u8>,
+            }
+            impl ::core::fmt::Debug for Response {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("Response")
+                        .field("status", &self.status)
+                        .field("headers", &self.headers)
+                        .field("body", &self.body)
+                        .finish()
+                }
+            }
+            /// Changes `modify-request` wants applied before forwarding.
+            #[derive(Clone)]
+            pub struct RequestOverride {
+                pub app_url: Option<_rt::String>,
+                pub method: Option<_rt::String>,
+                /// Upstream path (without query). Replaces the route's `rewrite` result.
+                pub path: Option<_rt::String>,
+                pub query_params_to_set: _rt::Vec<(_rt::String, _rt::String)>,
+                pub query_params_to_remove: _rt::Vec<_rt::String>,
+                pub headers_to_update: Headers,
+                pub headers_to_remove: _rt::Vec<_rt::String>,
+                pub response_headers_to_add: Headers,
+                pub response_headers_to_remove: _rt::Vec<_rt::String>,
+                /// Replacement request body. `content-length` is recomputed by rilot.
+                pub body: Option<_rt::Vec<u8>>,
+                /// When set, rilot returns this response without contacting the upstream.
+                pub response: Option<Response>,
+                pub context_to_set: Context,
+                pub context_to_remove: _rt::Vec<_rt::String>,
+                /// Skip the remaining overrides in the route's chain.
+                pub stop: bool,
+            }
+            impl ::core::fmt::Debug for RequestOverride {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("RequestOverride")
+                        .field("app-url", &self.app_url)
+                        .field("method", &self.method)
+                        .field("path", &self.path)
+                        .field("query-params-to-set", &self.query_params_to_set)
+                        .field("query-params-to-remove", &self.query_params_to_remove)
+                        .field("headers-to-update", &self.headers_to_update)
+                        .field("headers-to-remove", &self.headers_to_remove)
+                        .field("response-headers-to-add", &self.response_headers_to_add)
+                        .field(
+                            "response-headers-to-remove",
+                            &self.response_headers_to_remove,
+                        )
+                        .field("body", &self.body)
+                        .field("response", &self.response)
+                        .field("context-to-set", &self.context_to_set)
+                        .field("context-to-remove", &self.context_to_remove)
+                        .field("stop", &self.stop)
+                        .finish()
+                }
+            }
+            /// The upstream response as seen by `modify-response`.
+            #[derive(Clone)]
+            pub struct UpstreamResponse {
+                pub status: u16,
+                pub headers: Headers,
+                /// Raw upstream bytes; only present when the route sets `include_response_body`.
+                pub body: Option<_rt::Vec<u8>>,
+                /// The context left by the request chain.
+                pub context: Context,
+                /// The route's `override_config` as JSON text.
+                pub config: Option<_rt::String>,
+            }
+            impl ::core::fmt::Debug for UpstreamResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("UpstreamResponse")
+                        .field("status", &self.status)
+                        .field("headers", &self.headers)
+                        .field("body", &self.body)
+                        .field("context", &self.context)
+                        .field("config", &self.config)
+                        .finish()
+                }
+            }
+            /// Changes `modify-response` wants applied before replying downstream.
+            #[derive(Clone)]
+            pub struct ResponseOverride {
+                pub status: Option<u16>,
+                pub headers_to_update: Headers,
+                pub headers_to_remove: _rt::Vec<_rt::String>,
+                /// Replacement response body. `content-length` is recomputed by rilot.
+                pub body: Option<_rt::Vec<u8>>,
+                /// Skip the remaining response hooks in the route's chain.
+                pub stop: bool,
+            }
+            impl ::core::fmt::Debug for ResponseOverride {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("ResponseOverride")
+                        .field("status", &self.status)
+                        .field("headers-to-update", &self.headers_to_update)
+                        .field("headers-to-remove", &self.headers_to_remove)
+                        .field("body", &self.body)
+                        .field("stop", &self.stop)
+                        .finish()
+                }
+            }
+        }
+        /// Key-value store shared by all overrides and kept across requests.
+        /// Keys live in one namespace, so prefix them per module.
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod keyvalue {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            #[allow(unused_unsafe, clippy::all)]
+            /// Returns the value under `key`, unless it is missing or expired.
+            pub fn get(key: &str) -> Option<_rt::Vec<u8>> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = key;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "rilot:proxy/keyvalue@0.1.0")]
+                    unsafe extern "C" {
+                        #[link_name = "get"]
+                        fn wit_import2(_: *mut u8, _: usize, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import2(_: *mut u8, _: usize, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import2(ptr0.cast_mut(), len0, ptr1) };
+                    let l3 = i32::from(*ptr1.add(0).cast::<u8>());
+                    let result7 = match l3 {
+                        0 => None,
+                        1 => {
+                            let e = {
+                                let l4 = *ptr1
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l5 = *ptr1
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len6 = l5;
+                                _rt::Vec::from_raw_parts(l4.cast(), len6, len6)
+                            };
+                            Some(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result7
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// Stores `value` under `key`. With `ttl-ms` the key expires after that many milliseconds.
+            pub fn set(
+                key: &str,
+                value: &[u8],
+                ttl_ms: Option<u64>,
+            ) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = key;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let vec1 = value;
+                    let ptr1 = vec1.as_ptr().cast::<u8>();
+                    let len1 = vec1.len();
+                    let (result2_0, result2_1) = match ttl_ms {
+                        Some(e) => (1i32, _rt::as_i64(e)),
+                        None => (0i32, 0i64),
+                    };
+                    let ptr3 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "rilot:proxy/keyvalue@0.1.0")]
+                    unsafe extern "C" {
+                        #[link_name = "set"]
+                        fn wit_import4(
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: i32,
+                            _: i64,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import4(
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: i32,
+                        _: i64,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import4(
+                            ptr0.cast_mut(),
+                            len0,
+                            ptr1.cast_mut(),
+                            len1,
+                            result2_0,
+                            result2_1,
+                            ptr3,
+                        )
+                    };
+                    let l5 = i32::from(*ptr3.add(0).cast::<u8>());
+                    let result9 = match l5 {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l6 = *ptr3
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l7 = *ptr3
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len8 = l7;
+                                let bytes8 = _rt::Vec::from_raw_parts(
+                                    l6.cast(),
+                                    len8,
+                                    len8,
+                                );
+                                _rt::string_lift(bytes8)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result9
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// Removes `key`. Returns whether it existed.
+            pub fn delete(key: &str) -> bool {
+                unsafe {
+                    let vec0 = key;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "rilot:proxy/keyvalue@0.1.0")]
+                    unsafe extern "C" {
+                        #[link_name = "delete"]
+                        fn wit_import1(_: *mut u8, _: usize) -> i32;
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: *mut u8, _: usize) -> i32 {
+                        unreachable!()
+                    }
+                    let ret = unsafe { wit_import1(ptr0.cast_mut(), len0) };
+                    _rt::bool_lift(ret as u8)
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// Adds `delta` to the decimal integer under `key` (missing counts as 0) and
+            /// returns the new value. `ttl-ms` only applies when the key is created.
+            pub fn increment(
+                key: &str,
+                delta: i64,
+                ttl_ms: Option<u64>,
+            ) -> Result<i64, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 8 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 8
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = key;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let (result1_0, result1_1) = match ttl_ms {
+                        Some(e) => (1i32, _rt::as_i64(e)),
+                        None => (0i32, 0i64),
+                    };
+                    let ptr2 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "rilot:proxy/keyvalue@0.1.0")]
+                    unsafe extern "C" {
+                        #[link_name = "increment"]
+                        fn wit_import3(
+                            _: *mut u8,
+                            _: usize,
+                            _: i64,
+                            _: i32,
+                            _: i64,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import3(
+                        _: *mut u8,
+                        _: usize,
+                        _: i64,
+                        _: i32,
+                        _: i64,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import3(
+                            ptr0.cast_mut(),
+                            len0,
+                            _rt::as_i64(&delta),
+                            result1_0,
+                            result1_1,
+                            ptr2,
+                        )
+                    };
+                    let l4 = i32::from(*ptr2.add(0).cast::<u8>());
+                    let result9 = match l4 {
+                        0 => {
+                            let e = {
+                                let l5 = *ptr2.add(8).cast::<i64>();
+                                l5
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l6 = *ptr2.add(8).cast::<*mut u8>();
+                                let l7 = *ptr2
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len8 = l7;
+                                let bytes8 = _rt::Vec::from_raw_parts(
+                                    l6.cast(),
+                                    len8,
+                                    len8,
+                                );
+                                _rt::string_lift(bytes8)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result9
+                }
+            }
+        }
+        /// Guest logging routed into rilot's logger, tagged with the route,
+        /// module and request.
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod logging {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            #[repr(u8)]
+            #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+            pub enum Level {
+                Trace,
+                Debug,
+                Info,
+                Warn,
+                Error,
+            }
+            impl ::core::fmt::Debug for Level {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    match self {
+                        Level::Trace => f.debug_tuple("Level::Trace").finish(),
+                        Level::Debug => f.debug_tuple("Level::Debug").finish(),
+                        Level::Info => f.debug_tuple("Level::Info").finish(),
+                        Level::Warn => f.debug_tuple("Level::Warn").finish(),
+                        Level::Error => f.debug_tuple("Level::Error").finish(),
+                    }
+                }
+            }
+            impl Level {
+                #[doc(hidden)]
+                pub unsafe fn _lift(val: u8) -> Level {
+                    if !cfg!(debug_assertions) {
+                        return ::core::mem::transmute(val);
+                    }
+                    match val {
+                        0 => Level::Trace,
+                        1 => Level::Debug,
+                        2 => Level::Info,
+                        3 => Level::Warn,
+                        4 => Level::Error,
+                        _ => panic!("invalid enum discriminant"),
+                    }
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn log(level: Level, message: &str) -> () {
+                unsafe {
+                    let vec0 = message;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "rilot:proxy/logging@0.1.0")]
+                    unsafe extern "C" {
+                        #[link_name = "log"]
+                        fn wit_import1(_: i32, _: *mut u8, _: usize);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i32, _: *mut u8, _: usize) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(level.clone() as i32, ptr0.cast_mut(), len0) };
+                }
+            }
+        }
+    }
+}
 #[rustfmt::skip]
 mod _rt {
     #![allow(dead_code, clippy::all)]
+    pub use alloc_crate::vec::Vec;
+    pub use alloc_crate::string::String;
+    pub unsafe fn invalid_enum_discriminant<T>() -> T {
+        if cfg!(debug_assertions) {
+            panic!("invalid enum discriminant")
+        } else {
+            unsafe { core::hint::unreachable_unchecked() }
+        }
+    }
+    pub fn as_i64<T: AsI64>(t: T) -> i64 {
+        t.as_i64()
+    }
+    pub trait AsI64 {
+        fn as_i64(self) -> i64;
+    }
+    impl<'a, T: Copy + AsI64> AsI64 for &'a T {
+        fn as_i64(self) -> i64 {
+            (*self).as_i64()
+        }
+    }
+    impl AsI64 for i64 {
+        #[inline]
+        fn as_i64(self) -> i64 {
+            self as i64
+        }
+    }
+    impl AsI64 for u64 {
+        #[inline]
+        fn as_i64(self) -> i64 {
+            self as i64
+        }
+    }
+    pub unsafe fn string_lift(bytes: Vec<u8>) -> String {
+        if cfg!(debug_assertions) {
+            String::from_utf8(bytes).unwrap()
+        } else {
+            String::from_utf8_unchecked(bytes)
+        }
+    }
+    pub unsafe fn bool_lift(val: u8) -> bool {
+        if cfg!(debug_assertions) {
+            match val {
+                0 => false,
+                1 => true,
+                _ => panic!("invalid bool discriminant"),
+            }
+        } else {
+            val != 0
+        }
+    }
     #[cfg(target_arch = "wasm32")]
     pub fn run_ctors_once() {
         wit_bindgen_rt::run_ctors_once();
     }
+    pub unsafe fn cabi_dealloc(ptr: *mut u8, size: usize, align: usize) {
+        if size == 0 {
+            return;
+        }
+        let layout = alloc::Layout::from_size_align_unchecked(size, align);
+        alloc::dealloc(ptr, layout);
+    }
+    pub fn as_i32<T: AsI32>(t: T) -> i32 {
+        t.as_i32()
+    }
+    pub trait AsI32 {
+        fn as_i32(self) -> i32;
+    }
+    impl<'a, T: Copy + AsI32> AsI32 for &'a T {
+        fn as_i32(self) -> i32 {
+            (*self).as_i32()
+        }
+    }
+    impl AsI32 for i32 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for u32 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for i16 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for u16 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for i8 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for u8 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for char {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for usize {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    pub use alloc_crate::alloc;
+    extern crate alloc as alloc_crate;
 }
 /// Generates `#[unsafe(no_mangle)]` functions to export the specified type as
 /// the root implementation of all generated traits.
@@ -59,15 +1776,38 @@ macro_rules! __export_rilot_override_impl {
 pub(crate) use __export_rilot_override_impl as export;
 #[cfg(target_arch = "wasm32")]
 #[unsafe(
-    link_section = "component-type:wit-bindgen:0.41.0:local:rilot-override:rilot-override:encoded world"
+    link_section = "component-type:wit-bindgen:0.41.0:rilot:proxy@0.1.0:rilot-override:encoded world"
 )]
 #[doc(hidden)]
 #[allow(clippy::octal_escapes)]
-pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 202] = *b"\
-\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07F\x01A\x02\x01A\x02\x01\
-@\0\x01\0\x04\0\x0emodify-request\x01\0\x04\0#local:rilot-override/rilot-overrid\
-e\x04\0\x0b\x14\x01\0\x0erilot-override\x03\0\0\0G\x09producers\x01\x0cprocessed\
--by\x02\x0dwit-component\x070.227.1\x10wit-bindgen-rust\x060.41.0";
+pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 1259] = *b"\
+\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xe6\x08\x01A\x02\x01\
+A\x12\x01B\x16\x01o\x02ss\x01p\0\x04\0\x07headers\x03\0\x01\x01p\0\x04\0\x07cont\
+ext\x03\0\x03\x01p\0\x01p}\x01ks\x01r\x08\x06methods\x04paths\x05query\x05\x07he\
+aders\x02\x04body\x06\x07context\x04\x06config\x07\x06params\x05\x04\0\x07reques\
+t\x03\0\x08\x01r\x03\x06status{\x07headers\x02\x04body\x06\x04\0\x08response\x03\
+\0\x0a\x01ps\x01k\x06\x01k\x0b\x01r\x0e\x07app-url\x07\x06method\x07\x04path\x07\
+\x13query-params-to-set\x05\x16query-params-to-remove\x0c\x11headers-to-update\x02\
+\x11headers-to-remove\x0c\x17response-headers-to-add\x02\x1aresponse-headers-to-\
+remove\x0c\x04body\x0d\x08response\x0e\x0econtext-to-set\x04\x11context-to-remov\
+e\x0c\x04stop\x7f\x04\0\x10request-override\x03\0\x0f\x01r\x05\x06status{\x07hea\
+ders\x02\x04body\x0d\x07context\x04\x06config\x07\x04\0\x11upstream-response\x03\
+\0\x11\x01k{\x01r\x05\x06status\x13\x11headers-to-update\x02\x11headers-to-remov\
+e\x0c\x04body\x0d\x04stop\x7f\x04\0\x11response-override\x03\0\x14\x03\0\x17rilo\
+t:proxy/types@0.1.0\x05\0\x02\x03\0\0\x11upstream-response\x03\0\x11upstream-res\
+ponse\x03\0\x01\x02\x03\0\0\x11response-override\x03\0\x11response-override\x03\0\
+\x03\x02\x03\0\0\x07request\x03\0\x07request\x03\0\x05\x02\x03\0\0\x10request-ov\
+erride\x03\0\x10request-override\x03\0\x07\x01B\x0d\x01p}\x01k\0\x01@\x01\x03key\
+s\0\x01\x04\0\x03get\x01\x02\x01kw\x01j\0\x01s\x01@\x03\x03keys\x05value\0\x06tt\
+l-ms\x03\0\x04\x04\0\x03set\x01\x05\x01@\x01\x03keys\0\x7f\x04\0\x06delete\x01\x06\
+\x01j\x01x\x01s\x01@\x03\x03keys\x05deltax\x06ttl-ms\x03\0\x07\x04\0\x09incremen\
+t\x01\x08\x03\0\x1arilot:proxy/keyvalue@0.1.0\x05\x09\x01B\x04\x01m\x05\x05trace\
+\x05debug\x04info\x04warn\x05error\x04\0\x05level\x03\0\0\x01@\x02\x05level\x01\x07\
+messages\x01\0\x04\0\x03log\x01\x02\x03\0\x19rilot:proxy/logging@0.1.0\x05\x0a\x01\
+@\x01\x03res\x02\0\x04\x04\0\x0fmodify-response\x01\x0b\x01@\x01\x03req\x06\0\x08\
+\x04\0\x0emodify-request\x01\x0c\x04\0\x20rilot:proxy/rilot-override@0.1.0\x04\0\
+\x0b\x14\x01\0\x0erilot-override\x03\0\0\0G\x09producers\x01\x0cprocessed-by\x02\
+\x0dwit-component\x070.227.1\x10wit-bindgen-rust\x060.41.0";
 #[inline(never)]
 #[doc(hidden)]
 pub fn __link_custom_section_describing_imports() {
@@ -1,47 +1,41 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io::{self, Read, Write}; // Import Write trait
 use wasi_http_client::Client;
 use serde_json::{json, Value};
 
 
 wit_bindgen::generate!({
-    path: "interface.wit",     // Keep explicit path
-    world: "rilot-override",  // Must match world name in interface.wit
+    path: "../wit",           // Shared `rilot:proxy` package
+    world: "rilot-override",  // Must match world name in wit/rilot.wit
 });
 
-#[derive(Deserialize, Serialize, Debug, Default)]
-struct InternalWasmInput {
-    method: String,
-    path: String,
-    #[serde(default)]
-    headers: HashMap<String, String>,
-    #[serde(default)]
-    body: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
-struct InternalWasmOutput {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    app_url: Option<String>,
-    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
-    headers_to_update: HashMap<String, String>,
-    #[serde(skip_serializing_if = "Vec::is_empty", default)]
-    headers_to_remove: Vec<String>,
-    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
-    response_headers_to_add: HashMap<String, String>,
-    #[serde(skip_serializing_if = "Vec::is_empty", default)]
-    response_headers_to_remove: Vec<String>,
-}
+use rilot::proxy::keyvalue;
+use rilot::proxy::logging::{self, Level};
+use rilot::proxy::types::Response;
 
 struct MyComponent;
 
 impl Guest for MyComponent {
-    fn modify_request() {
-        eprintln!("[Wasm/lib.rs] modify_request called. Reading stdin...");
-        let input: InternalWasmInput = read_and_parse_stdin();
+    fn modify_request(req: Request) -> RequestOverride {
+        logging::log(Level::Debug, &format!("modify_request called for {} {}", req.method, req.path));
+
+        // Example: answer directly from Wasm without contacting the upstream
+        if req.path == "/maintenance" {
+            let mut output = empty_request_override();
+            output.response = Some(Response {
+                status: 503,
+                headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+                body: b"Down for maintenance.".to_vec(),
+            });
+            return output;
+        }
+
         let client = Client::new();
-        let external_api_url = "http://127.0.0.1:3012/category/sample"; // Example API
+        // Example: read settings from the route's `override_config`
+        let settings: Value = req.config.as_deref()
+            .and_then(|c| serde_json::from_str(c).ok())
+            .unwrap_or(Value::Null);
+        let external_api_url = settings.get("api_url")
+            .and_then(Value::as_str)
+            .unwrap_or("http://127.0.0.1:3012/category/sample"); // Example API
 
         let custom_payload = json!({ // Example custom body
             "name": "test",
@@ -50,13 +44,13 @@ impl Guest for MyComponent {
         });
         let api_request_body = match serde_json::to_vec(&custom_payload) {
             Ok(body) => body,
-            Err(_e) => { /* ... handle error ... */ write_output_and_exit(&InternalWasmOutput::default()); return; }
+            Err(_e) => { /* ... handle error ... */ return empty_request_override(); }
         };
 
-        eprintln!("[Wasm/lib.rs] Sending request to external API: {}", external_api_url);
-        let mut request_builder = client.post(external_api_url) /* ... headers ... */;
+        logging::log(Level::Debug, &format!("Sending request to external API: {}", external_api_url));
+        let request_builder = client.post(external_api_url) /* ... headers ... */;
         // ... (add headers loop remains the same) ...
-        // for (key, value) in &input.headers {
+        // for (key, value) in &req.headers {
         //      if key.to_lowercase() != "host" && key.to_lowercase() != "content-length" {
         //           request_builder = request_builder.header(key, value.as_str());
         //      }
@@ -65,90 +59,109 @@ impl Guest for MyComponent {
 
         let resp = match resp_result {
             Ok(r) => { /* ... */ r },
-            Err(_e) => { /* ... handle error ... */ write_output_and_exit(&InternalWasmOutput::default()); return; }
+            Err(_e) => { /* ... handle error ... */ return empty_request_override(); }
         };
         let body_result = resp.body();
         let body_bytes = match body_result {
              Ok(b) => { /* ... */ b },
-             Err(_e) => { /* ... */ write_output_and_exit(&InternalWasmOutput::default()); return; }
+             Err(_e) => { /* ... */ return empty_request_override(); }
         };
 
-        eprintln!("[Wasm/lib.rs] Attempting to parse API response body as JSON Value...");
-        let mut final_output = InternalWasmOutput::default();
+        logging::log(Level::Debug, "Attempting to parse API response body as JSON Value...");
+        let mut final_output = empty_request_override();
 
         match serde_json::from_slice::<Value>(&body_bytes) {
             Ok(api_response_value) => {
-                eprintln!("[Wasm/lib.rs] Successfully parsed API response as JSON Value.");
+                logging::log(Level::Debug, "Successfully parsed API response as JSON Value.");
                 if let Value::Object(map) = api_response_value {
-                    eprintln!("[Wasm/lib.rs] API response is a JSON object. Extracting fields...");
+                    logging::log(Level::Debug, "API response is a JSON object. Extracting fields...");
 
                     final_output.app_url = map.get("app_url").or_else(|| map.get("target_backend_url")).and_then(Value::as_str).map(String::from);
                     final_output.headers_to_update = map.get("headers_to_update").or_else(|| map.get("extra_request_headers")).and_then(Value::as_object).map(|obj| obj.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect()).unwrap_or_default();
                     final_output.headers_to_remove = map.get("headers_to_remove").or_else(|| map.get("strip_request_headers")).and_then(Value::as_array).map(|arr| arr.iter().filter_map(Value::as_str).map(String::from).collect()).unwrap_or_default();
                     final_output.response_headers_to_add = map.get("response_headers_to_add").or_else(|| map.get("final_response_headers")).and_then(Value::as_object).map(|obj| obj.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect()).unwrap_or_default();
                     final_output.response_headers_to_remove = map.get("response_headers_to_remove").or_else(|| map.get("strip_response_headers")).and_then(Value::as_array).map(|arr| arr.iter().filter_map(Value::as_str).map(String::from).collect()).unwrap_or_default();
-                    eprintln!("[Wasm/lib.rs] Extracted data: {:?}", final_output);
+                    logging::log(Level::Debug, &format!("Extracted data: {:?}", final_output));
                 } else {
-                    eprintln!("[Wasm/lib.rs] WARNING: API response was valid JSON but not an object.");
+                    logging::log(Level::Warn, "API response was valid JSON but not an object.");
                 }
             },
             Err(_e) => {
-                eprintln!("[Wasm/lib.rs] ERROR: Failed to parse JSON response from external API: {:?}", _e);
+                logging::log(Level::Error, &format!("Failed to parse JSON response from external API: {:?}", _e));
                 match std::str::from_utf8(&body_bytes) {
-                    Ok(s) => eprintln!("[Wasm/lib.rs] Raw API response body: {}", s),
-                    Err(_) => eprintln!("[Wasm/lib.rs] Raw API response body was not valid UTF-8."),
+                    Ok(s) => logging::log(Level::Debug, &format!("Raw API response body: {}", s)),
+                    Err(_) => logging::log(Level::Debug, "Raw API response body was not valid UTF-8."),
                 }
             }
         };
 
-        // Example: Manually add/override response header AFTER parsing/extracting
-        final_output.headers_to_update.insert(
+        // Example: tell later overrides in the chain that the API was consulted
+        final_output.context_to_set.push((
+            "example.api-checked".to_string(),
+            "true".to_string()
+        ));
+
+        // Example: count requests across calls with the host key-value store
+        if let Ok(hits) = keyvalue::increment("example:hits", 1, None) {
+            final_output.headers_to_update.push(("X-Rilot-Hits".to_string(), hits.to_string()));
+        }
+
+        // Example: Manually add/override request header AFTER parsing/extracting
+        final_output.headers_to_update.push((
             "X-Via-Rilot".to_string(),
             "Yes".to_string()
-        );
+        ));
 
-        eprintln!("[Wasm/lib.rs] Final app_url to suggest: {:?}", final_output.app_url);
+        logging::log(Level::Debug, &format!("Final app_url to suggest: {:?}", final_output.app_url));
 
-        write_output_and_exit(&final_output);
+        final_output
     }
-}
 
+    fn modify_response(res: UpstreamResponse) -> ResponseOverride {
+        logging::log(Level::Debug, &format!("modify_response called. Upstream responded with status {}", res.status));
 
-fn read_and_parse_stdin() -> InternalWasmInput {
-    eprintln!("[Wasm/lib.rs] Reading stdin...");
-    let mut input_json_string = String::new();
-    if let Err(_e) = io::stdin().read_to_string(&mut input_json_string) {
-        eprintln!("[Wasm/lib.rs] ERROR: Failed to read from stdin: {:?}", _e);
-        return InternalWasmInput::default();
-    }
-    eprintln!("[Wasm/lib.rs] Read {} bytes from stdin.", input_json_string.len());
-    match serde_json::from_str(&input_json_string) {
-        Ok(parsed) => parsed,
-        Err(e) => {
-            eprintln!("[Wasm/lib.rs] ERROR: Failed to parse stdin JSON: {:?}", e);
-            InternalWasmInput::default()
+        let mut final_output = ResponseOverride {
+            status: None,
+            headers_to_update: Vec::new(),
+            headers_to_remove: Vec::new(),
+            body: None,
+            stop: false,
+        };
+
+        // Example: scrub upstream implementation details and add security headers
+        final_output.headers_to_remove.push("server".to_string());
+        final_output.headers_to_update.push((
+            "X-Content-Type-Options".to_string(),
+            "nosniff".to_string()
+        ));
+
+        // Example: replace upstream error pages with a generic body
+        if res.status >= 500 {
+            final_output.body = Some(b"Upstream service unavailable.".to_vec());
         }
+
+        final_output
     }
 }
 
 
-fn write_output_and_exit(output: &InternalWasmOutput) {
-    match serde_json::to_string_pretty(output) {
-        Ok(output_json) => {
-            eprintln!("[Wasm/lib.rs] Writing output JSON to std::io::stdout ({} bytes)", output_json.len());
-            println!("{}", output_json);
-            if let Err(_e) = io::stdout().flush() {
-                eprintln!("[Wasm/lib.rs] ERROR: Failed to flush std::io::stdout: {:?}", _e);
-            }
-        }
-        Err(_e) => {
-            eprintln!("[Wasm/lib.rs] ERROR: Failed to serialize final output: {:?}", _e);
-            let error_json = "{{\"error\":\"serialization failed\"}}";
-            println!("{}", error_json);
-            io::stdout().flush().ok(); // Flush the fallback
-        }
+fn empty_request_override() -> RequestOverride {
+    RequestOverride {
+        app_url: None,
+        method: None,
+        path: None,
+        query_params_to_set: Vec::new(),
+        query_params_to_remove: Vec::new(),
+        headers_to_update: Vec::new(),
+        headers_to_remove: Vec::new(),
+        response_headers_to_add: Vec::new(),
+        response_headers_to_remove: Vec::new(),
+        body: None,
+        response: None,
+        context_to_set: Vec::new(),
+        context_to_remove: Vec::new(),
+        stop: false,
     }
-    eprintln!("[Wasm/lib.rs] modify_request finished.");
 }
 
 
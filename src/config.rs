@@ -1,10 +1,51 @@
+use anyhow::Context;
 use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::collections::HashMap;
+
+use crate::router::Router;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProxyRule {
     pub path: String,
+    /// `exact`, `prefix` (alias `contain`), `regex` or `glob`.
     #[serde(rename = "type", default = "default_rule_type")]
     pub r#type: String,
+    /// Virtual host this route belongs to; unset routes serve any other host.
+    #[serde(default)]
+    pub host: Option<String>,
+    /// `exact`, `wildcard` or `regex`. Defaults to `wildcard` for hosts
+    /// starting with `*.` and `exact` otherwise.
+    #[serde(default)]
+    pub host_type: Option<String>,
+    /// HTTP methods the route accepts; empty accepts any.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    #[serde(default)]
+    pub headers: Vec<ValuePredicate>,
+    #[serde(default)]
+    pub query: Vec<ValuePredicate>,
+    #[serde(default)]
+    pub cookies: Vec<ValuePredicate>,
+    /// Routes with a higher priority are tried first. Within a priority,
+    /// exact rules beat regex and glob rules, which beat prefix rules
+    /// (longest first).
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A header, query parameter or cookie the route requires. With neither
+/// `equals` nor `regex`, the value only has to be present (or, with
+/// `present: false`, absent).
+#[derive(Debug, Deserialize, Clone)]
+pub struct ValuePredicate {
+    pub name: String,
+    #[serde(default)]
+    pub equals: Option<String>,
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub present: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -13,14 +54,220 @@ pub struct ProxyConfig {
     pub app_uri: String,
     #[serde(default)]
     pub override_file: Option<String>,
+    #[serde(default)]
+    pub overrides: Vec<String>, // run in order after `override_file`
+    #[serde(default)]
+    pub override_config: Option<Box<RawValue>>, // passed to the overrides verbatim
     pub rule: ProxyRule,
     #[serde(default = "default_rewrite_mode")]
     pub rewrite: String,
+    #[serde(default)]
+    pub include_response_body: bool, // buffer the upstream body for `modify-response`
+    #[serde(default)]
+    pub limits: WasmLimits,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub on_override_error: OnOverrideError,
+}
+
+impl ProxyConfig {
+    /// The route's overrides in execution order.
+    pub fn override_chain(&self) -> Vec<&str> {
+        self.override_file
+            .iter()
+            .chain(self.overrides.iter())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// What a route does when one of its overrides fails.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum OnOverrideError {
+    /// Reply with an error. Without `status`, limit violations map to
+    /// 504/503 and other failures to 500.
+    FailClosed {
+        #[serde(default)]
+        status: Option<u16>,
+        #[serde(default)]
+        body: Option<String>,
+    },
+    /// Ignore the failed override's output and carry on.
+    FailOpen,
+    /// Skip the remaining overrides and forward to `app_uri` instead.
+    Fallback { app_uri: String },
+}
+
+impl Default for OnOverrideError {
+    fn default() -> Self {
+        OnOverrideError::FailClosed { status: None, body: None }
+    }
+}
+
+/// Per-route execution limits for Wasm overrides. Unset means unlimited,
+/// except for `max_output_bytes`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct WasmLimits {
+    #[serde(default)]
+    pub fuel: Option<u64>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub max_memory_bytes: Option<usize>,
+    /// Captured stdout and stderr per call, each. Unset means 64 KiB.
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+}
+
+impl WasmLimits {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.max_output_bytes == Some(0) {
+            anyhow::bail!("limits.max_output_bytes must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// WASI capabilities granted to a route's overrides. Guests get no
+/// environment and no filesystem unless listed here.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SandboxConfig {
+    /// Proxy environment variables copied into the guest, by name.
+    #[serde(default)]
+    pub env_allowlist: Vec<String>,
+    /// Explicit guest environment variables.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Host directories mounted read-only in the guest.
+    #[serde(default)]
+    pub preopens: Vec<PreopenDir>,
+    /// Hosts reachable via wasi-http (`host`, `host:port` or `*.domain`).
+    /// Unset allows any host; an empty list blocks all outbound requests.
+    #[serde(default)]
+    pub allowed_hosts: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PreopenDir {
+    pub host_path: String,
+    pub guest_path: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub proxies: Vec<ProxyConfig>,
+    /// Overrides run on every request before a route is matched.
+    #[serde(default)]
+    pub pre_routing: Vec<String>,
+    /// Overrides run on every routed request after the route's own chain.
+    #[serde(default)]
+    pub post_routing: Vec<String>,
+    /// Limits for the global overrides.
+    #[serde(default)]
+    pub global_limits: WasmLimits,
+    /// WASI capabilities for the global overrides.
+    #[serde(default)]
+    pub global_sandbox: SandboxConfig,
+    /// JSON passed to the global overrides as `config`.
+    #[serde(default)]
+    pub global_override_config: Option<Box<RawValue>>,
+    /// Failure policy for the global overrides.
+    #[serde(default)]
+    pub global_on_override_error: OnOverrideError,
+    #[serde(default)]
+    pub engine: EngineConfig,
+    #[serde(default)]
+    pub kv: KvConfig,
+    /// Development conveniences: failed overrides reply with their error,
+    /// trap code and backtrace instead of a generic body.
+    #[serde(default)]
+    pub dev_mode: bool,
+    /// Route rules compiled by `load_config`.
+    #[serde(skip)]
+    pub router: Router,
+}
+
+impl Config {
+    /// Every distinct override component referenced by the config.
+    pub fn override_files(&self) -> Vec<&str> {
+        let mut files: Vec<&str> = Vec::new();
+        let global = self.pre_routing.iter().chain(self.post_routing.iter()).map(String::as_str);
+        for file in global.chain(self.proxies.iter().flat_map(|p| p.override_chain())) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+        files
+    }
+}
+
+/// Host key-value store shared by every override.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct KvConfig {
+    /// Cap on stored keys; `set` fails once it is reached.
+    pub max_entries: usize,
+    /// JSON file the store is loaded from at startup and flushed to.
+    pub persist_path: Option<String>,
+    /// How often expired keys are purged and the file is flushed.
+    pub flush_interval_ms: u64,
+}
+
+impl Default for KvConfig {
+    fn default() -> Self {
+        KvConfig { max_entries: 10_000, persist_path: None, flush_interval_ms: 5_000 }
+    }
+}
+
+/// Process-wide Wasm engine settings.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EngineConfig {
+    /// Pooling allocator sizes. Unset uses on-demand allocation, which has
+    /// no cap on concurrent instances; with a pool, calls beyond its slots
+    /// fail with a 503.
+    #[serde(default)]
+    pub pooling: Option<PoolingConfig>,
+    /// Poll loaded `.wasm` files and recompile changed ones in the background.
+    #[serde(default)]
+    pub watch_interval_ms: Option<u64>,
+    /// Resolve guest backtrace frames to function, file and line names using
+    /// the module's DWARF debug info. Slows down compilation.
+    #[serde(default)]
+    pub backtrace_details: bool,
+    /// Load the `.cwasm` artifacts written by `rilot compile` instead of
+    /// compiling overrides at startup. Artifacts are native code loaded
+    /// without verification, so only enable this when nothing untrusted can
+    /// write next to the `.wasm` files.
+    #[serde(default)]
+    pub load_precompiled: bool,
+}
+
+/// Slots pre-reserved by wasmtime's pooling instance allocator. A single
+/// component usually needs several core instances, memories and tables.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PoolingConfig {
+    pub total_component_instances: u32,
+    pub total_core_instances: u32,
+    pub total_memories: u32,
+    pub total_tables: u32,
+    pub total_stacks: u32,
+    pub max_memory_size: usize,
+}
+
+impl Default for PoolingConfig {
+    fn default() -> Self {
+        PoolingConfig {
+            total_component_instances: 100,
+            total_core_instances: 400,
+            total_memories: 200,
+            total_tables: 400,
+            total_stacks: 100,
+            max_memory_size: 64 << 20,
+        }
+    }
 }
 
 fn default_rule_type() -> String {
@@ -29,9 +276,17 @@ fn default_rule_type() -> String {
 
 use std::fs;
 
-pub fn load_config(path: &str) -> Config {
-    let data = fs::read_to_string(path).expect("Failed to read config.json");
-    serde_json::from_str(&data).expect("Failed to parse config.json")
+/// Reads and parses the config, then compiles its route rules.
+pub fn load_config(path: &str) -> anyhow::Result<Config> {
+    let data = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let mut config: Config =
+        serde_json::from_str(&data).with_context(|| format!("Failed to parse {}", path))?;
+    config.global_limits.validate().context("global_limits")?;
+    for proxy in &config.proxies {
+        proxy.limits.validate().with_context(|| format!("route '{}'", proxy.app_name))?;
+    }
+    config.router = Router::new(&config.proxies)?;
+    Ok(config)
 }
 
 
@@ -0,0 +1,76 @@
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+
+/// `log` target for everything a guest prints or logs, so `RUST_LOG`
+/// can filter it separately (e.g. `RUST_LOG=info,rilot::guest=debug`).
+pub const TARGET: &str = "rilot::guest";
+
+/// Bytes of stdout/stderr kept per stream for one guest call, unless the
+/// route sets `limits.max_output_bytes`.
+const DEFAULT_OUTPUT_CAPACITY: usize = 64 * 1024;
+
+/// Attributes guest log lines to a route, module and request. Captured
+/// stdout/stderr is emitted when the store is dropped, so output from
+/// guests that trap or time out is still logged.
+pub struct GuestLog {
+    app_name: String,
+    module: String,
+    request_id: String,
+    capacity: usize,
+    stdout: MemoryOutputPipe,
+    stderr: MemoryOutputPipe,
+}
+
+impl GuestLog {
+    pub fn new(app_name: &str, module: &str, request_id: &str, capacity: Option<usize>) -> Self {
+        let capacity = capacity.unwrap_or(DEFAULT_OUTPUT_CAPACITY);
+        GuestLog {
+            app_name: app_name.to_string(),
+            module: module.to_string(),
+            request_id: request_id.to_string(),
+            capacity,
+            stdout: MemoryOutputPipe::new(capacity),
+            stderr: MemoryOutputPipe::new(capacity),
+        }
+    }
+
+    /// Whether stdout or stderr is full, so further guest writes fail.
+    pub fn overflowed(&self) -> bool {
+        self.stdout.contents().len() >= self.capacity || self.stderr.contents().len() >= self.capacity
+    }
+
+    /// Pipes to hand to the WASI context as stdout and stderr.
+    pub fn pipes(&self) -> (MemoryOutputPipe, MemoryOutputPipe) {
+        (self.stdout.clone(), self.stderr.clone())
+    }
+
+    pub fn log(&self, level: log::Level, message: &str) {
+        log::log!(
+            target: TARGET,
+            level,
+            "[app={} module={} req={}] {}",
+            self.app_name, self.module, self.request_id, message
+        );
+    }
+
+    fn emit_captured(&self, pipe: &MemoryOutputPipe, level: log::Level) {
+        let contents = pipe.contents();
+        if contents.is_empty() || !log::log_enabled!(target: TARGET, level) {
+            return;
+        }
+        for line in String::from_utf8_lossy(&contents).lines() {
+            if !line.trim().is_empty() {
+                self.log(level, line);
+            }
+        }
+    }
+}
+
+impl Drop for GuestLog {
+    fn drop(&mut self) {
+        if self.overflowed() {
+            self.log(log::Level::Warn, &format!("output truncated at {} bytes (limits.max_output_bytes)", self.capacity));
+        }
+        self.emit_captured(&self.stdout, log::Level::Info);
+        self.emit_captured(&self.stderr, log::Level::Warn);
+    }
+}
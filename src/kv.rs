@@ -0,0 +1,279 @@
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::config::KvConfig;
+
+/// Process-wide store behind the `rilot:proxy/keyvalue` import. All
+/// overrides share one namespace, so guests should prefix their keys.
+pub struct KvStore {
+    entries: Mutex<HashMap<String, Entry>>,
+    max_entries: usize,
+    persist_path: Option<PathBuf>,
+    /// Set by every write; cleared when the store is flushed to disk.
+    dirty: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    value: Vec<u8>,
+    /// Unix time in milliseconds after which the entry is gone.
+    expires_at_ms: Option<u64>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at_ms.is_some_and(|at| at <= now)
+    }
+}
+
+static STORE: OnceCell<KvStore> = OnceCell::new();
+
+/// Builds the shared store from `config.kv`, loading the persisted entries
+/// when `persist_path` is set.
+pub fn init(cfg: &KvConfig) -> Result<()> {
+    let store = KvStore::open(cfg)?;
+    STORE.set(store).map_err(|_| anyhow!("Key-value store already initialized"))
+}
+
+pub fn store() -> &'static KvStore {
+    STORE.get_or_init(|| KvStore::open(&KvConfig::default()).expect("Failed to create key-value store"))
+}
+
+/// Periodically drops expired entries and flushes the store to its file.
+pub fn spawn_maintenance(interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let store = store();
+            let purged = store.purge_expired();
+            if purged > 0 {
+                log::debug!("🧹 Purged {} expired key-value entries", purged);
+            }
+            if let Err(e) = store.flush() {
+                log::error!("❌ Failed to persist key-value store: {:#}", e);
+            }
+        }
+    });
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl KvStore {
+    fn open(cfg: &KvConfig) -> Result<Self> {
+        let persist_path = cfg.persist_path.as_ref().map(PathBuf::from);
+        let mut entries: HashMap<String, Entry> = HashMap::new();
+
+        if let Some(path) = persist_path.as_ref().filter(|p| p.exists()) {
+            let data = fs::read(path)
+                .with_context(|| format!("Failed to read key-value store: {}", path.display()))?;
+            entries = serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse key-value store: {}", path.display()))?;
+            let now = now_ms();
+            entries.retain(|_, entry| !entry.is_expired(now));
+            log::info!("🗄️ Loaded {} key-value entries from {}", entries.len(), path.display());
+        }
+
+        Ok(KvStore {
+            entries: Mutex::new(entries),
+            max_entries: cfg.max_entries,
+            persist_path,
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().expect("Key-value store lock poisoned")
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut entries = self.lock();
+        match entries.get(key) {
+            Some(entry) if entry.is_expired(now_ms()) => {
+                entries.remove(key);
+                self.dirty.store(true, Ordering::Relaxed);
+                None
+            }
+            Some(entry) => Some(entry.value.clone()),
+            None => None,
+        }
+    }
+
+    /// Stores `value` under `key`, replacing any previous value and TTL.
+    pub fn set(&self, key: String, value: Vec<u8>, ttl_ms: Option<u64>) -> Result<(), String> {
+        let now = now_ms();
+        let mut entries = self.lock();
+        self.ensure_capacity(&mut entries, &key, now)?;
+        entries.insert(key, Entry { value, expires_at_ms: ttl_ms.map(|ttl| now.saturating_add(ttl)) });
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Removes `key`. Returns whether a live entry was removed.
+    pub fn delete(&self, key: &str) -> bool {
+        let removed = self.lock().remove(key);
+        if removed.is_some() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        removed.is_some_and(|entry| !entry.is_expired(now_ms()))
+    }
+
+    /// Adds `delta` to the decimal integer stored under `key`, treating a
+    /// missing key as 0. `ttl_ms` only applies when the key is created, so a
+    /// counter keeps its original window.
+    pub fn increment(&self, key: String, delta: i64, ttl_ms: Option<u64>) -> Result<i64, String> {
+        let now = now_ms();
+        let mut entries = self.lock();
+        let live = entries.get(&key).filter(|entry| !entry.is_expired(now)).cloned();
+
+        let (current, expires_at_ms) = match live {
+            Some(entry) => {
+                let current = str::from_utf8(&entry.value)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .ok_or_else(|| format!("value of '{}' is not an integer", key))?;
+                (current, entry.expires_at_ms)
+            }
+            None => {
+                self.ensure_capacity(&mut entries, &key, now)?;
+                (0, ttl_ms.map(|ttl| now.saturating_add(ttl)))
+            }
+        };
+
+        let next = current
+            .checked_add(delta)
+            .ok_or_else(|| format!("incrementing '{}' overflows", key))?;
+        entries.insert(key, Entry { value: next.to_string().into_bytes(), expires_at_ms });
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(next)
+    }
+
+    /// Fails when inserting a new `key` would exceed `max_entries`, after
+    /// first making room by dropping expired entries.
+    fn ensure_capacity(&self, entries: &mut HashMap<String, Entry>, key: &str, now: u64) -> Result<(), String> {
+        if entries.contains_key(key) || entries.len() < self.max_entries {
+            return Ok(());
+        }
+        entries.retain(|_, entry| !entry.is_expired(now));
+        if entries.len() < self.max_entries {
+            return Ok(());
+        }
+        Err(format!("key-value store is full ({} entries)", self.max_entries))
+    }
+
+    fn purge_expired(&self) -> usize {
+        let now = now_ms();
+        let mut entries = self.lock();
+        let before = entries.len();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        let purged = before - entries.len();
+        if purged > 0 {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        purged
+    }
+
+    /// Writes the store to `persist_path` if it changed since the last flush.
+    pub fn flush(&self) -> Result<()> {
+        let Some(path) = &self.persist_path else { return Ok(()) };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let data = serde_json::to_vec(&*self.lock())?;
+        let tmp = path.with_extension("tmp");
+        let written = fs::write(&tmp, data).and_then(|_| fs::rename(&tmp, path));
+        if let Err(e) = written {
+            self.dirty.store(true, Ordering::Relaxed);
+            return Err(e).with_context(|| format!("Failed to write key-value store: {}", path.display()));
+        }
+        log::debug!("💾 Flushed key-value store to {}", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(max_entries: usize, persist_path: Option<String>) -> KvStore {
+        KvStore::open(&KvConfig { max_entries, persist_path, flush_interval_ms: 1_000 }).unwrap()
+    }
+
+    #[test]
+    fn expired_entries_are_gone() {
+        let store = open(10, None);
+        store.set("a".into(), b"1".to_vec(), Some(0)).unwrap();
+        store.set("b".into(), b"2".to_vec(), Some(60_000)).unwrap();
+        assert_eq!(store.get("a"), None);
+        assert_eq!(store.get("b"), Some(b"2".to_vec()));
+
+        store.set("c".into(), b"3".to_vec(), Some(0)).unwrap();
+        assert!(!store.delete("c"));
+        assert_eq!(store.increment("c".into(), 5, None), Ok(5));
+        assert_eq!(store.purge_expired(), 0);
+    }
+
+    #[test]
+    fn increment_counts_and_rejects_bad_values() {
+        let store = open(10, None);
+        assert_eq!(store.increment("n".into(), 2, None), Ok(2));
+        assert_eq!(store.increment("n".into(), -5, None), Ok(-3));
+        assert_eq!(store.get("n"), Some(b"-3".to_vec()));
+
+        store.set("max".into(), i64::MAX.to_string().into_bytes(), None).unwrap();
+        assert!(store.increment("max".into(), 1, None).is_err());
+        assert_eq!(store.get("max"), Some(i64::MAX.to_string().into_bytes()));
+
+        store.set("text".into(), b"abc".to_vec(), None).unwrap();
+        assert!(store.increment("text".into(), 1, None).is_err());
+        store.set("bytes".into(), vec![0xff], None).unwrap();
+        assert!(store.increment("bytes".into(), 1, None).is_err());
+    }
+
+    #[test]
+    fn max_entries_caps_new_keys() {
+        let store = open(2, None);
+        store.set("a".into(), b"1".to_vec(), None).unwrap();
+        store.set("b".into(), b"2".to_vec(), Some(0)).unwrap();
+        // The expired entry is dropped to make room.
+        store.set("c".into(), b"3".to_vec(), None).unwrap();
+        assert!(store.set("d".into(), b"4".to_vec(), None).is_err());
+        assert!(store.increment("d".into(), 1, None).is_err());
+        // Existing keys can still be overwritten.
+        store.set("a".into(), b"5".to_vec(), None).unwrap();
+        assert_eq!(store.increment("c".into(), 1, None), Ok(4));
+    }
+
+    #[test]
+    fn flush_persists_live_entries() {
+        let path = std::env::temp_dir().join(format!("rilot-kv-test-{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap().to_string();
+        let store = open(10, Some(path_str.clone()));
+        store.set("keep".into(), b"1".to_vec(), None).unwrap();
+        store.set("gone".into(), b"2".to_vec(), Some(0)).unwrap();
+        store.flush().unwrap();
+
+        let reopened = open(10, Some(path_str));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.get("keep"), Some(b"1".to_vec()));
+        assert_eq!(reopened.get("gone"), None);
+    }
+}
@@ -1,7 +1,11 @@
 use std::sync::Arc;
 use std::env;
+use std::time::Duration;
 mod config;
+mod guest_log;
+mod kv;
 mod proxy;
+mod router;
 mod wasm_engine;
 
 #[tokio::main]
@@ -9,21 +13,97 @@ async fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
     let args: Vec<String> = env::args().collect();
-    let config_path = args.get(1).map_or("./config.json", |p| p.as_str());
+    let compile_only = args.get(1).is_some_and(|a| a == "compile");
+    let config_arg = if compile_only { args.get(2) } else { args.get(1) };
+    let config_path = config_arg.map_or("./config.json", |p| p.as_str());
 
     log::info!("🛠️ Loading configuration from: {}", config_path);
 
-    let cfg = config::load_config(config_path);
+    let cfg = match config::load_config(config_path) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            log::error!("❌ Invalid configuration: {:#}", e);
+            std::process::exit(1);
+        }
+    };
     log::info!("✅ Configuration loaded successfully.");
 
     if cfg.proxies.is_empty() {
         log::warn!("⚠️ No proxy rules defined in the configuration.");
     }
 
+    if let Err(e) = wasm_engine::init(&cfg.engine) {
+        log::error!("❌ Failed to initialize Wasm engine: {:#}", e);
+        std::process::exit(1);
+    }
+
+    if compile_only {
+        std::process::exit(compile_overrides(&cfg));
+    }
+
+    if !validate_overrides(&cfg).await {
+        log::error!("❌ Refusing to start: fix the Wasm overrides above.");
+        std::process::exit(1);
+    }
+    if let Err(e) = kv::init(&cfg.kv) {
+        log::error!("❌ Failed to initialize key-value store: {:#}", e);
+        std::process::exit(1);
+    }
+    kv::spawn_maintenance(Duration::from_millis(cfg.kv.flush_interval_ms.max(1)));
+    if let Some(ms) = cfg.engine.watch_interval_ms {
+        wasm_engine::spawn_watcher(Duration::from_millis(ms));
+    }
+
     let config_arc = Arc::new(cfg);
 
     log::info!("🚀 Starting proxy server...");
     proxy::start_proxy(config_arc).await;
 
+    if let Err(e) = kv::store().flush() {
+        log::error!("❌ Failed to persist key-value store: {:#}", e);
+    }
     log::info!("👋 Proxy server shut down.");
-}
\ No newline at end of file
+}
+
+/// Compiles and type-checks every global and per-route override, logging one error per broken override.
+async fn validate_overrides(cfg: &config::Config) -> bool {
+    let mut healthy = true;
+    let global = [("pre-routing", &cfg.pre_routing), ("post-routing", &cfg.post_routing)];
+    for (stage, file) in global.iter().flat_map(|(stage, files)| files.iter().map(move |f| (stage, f))) {
+        match wasm_engine::validate(file).await {
+            Ok(()) => log::info!("✅ Global {} override {} is valid", stage, file),
+            Err(e) => {
+                log::error!("❌ Global {} override {} is invalid: {:#}", stage, file, e);
+                healthy = false;
+            }
+        }
+    }
+    for proxy in &cfg.proxies {
+        for file in proxy.override_chain() {
+            match wasm_engine::validate(file).await {
+                Ok(()) => log::info!("✅ Route '{}' ({}): override {} is valid", proxy.app_name, proxy.rule.path, file),
+                Err(e) => {
+                    log::error!("❌ Route '{}' ({}): override {} is invalid: {:#}", proxy.app_name, proxy.rule.path, file, e);
+                    healthy = false;
+                }
+            }
+        }
+    }
+    healthy
+}
+
+/// `rilot compile [config]`: writes a `.cwasm` artifact for every override.
+fn compile_overrides(cfg: &config::Config) -> i32 {
+    let mut failed = 0;
+    for file in cfg.override_files() {
+        log::info!("⚙️ Precompiling {}", file);
+        match wasm_engine::precompile(file) {
+            Ok(artifact) => log::info!("✅ Wrote {}", artifact.display()),
+            Err(e) => {
+                log::error!("❌ {:#}", e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 { 1 } else { 0 }
+}
@@ -1,26 +1,32 @@
 use hyper::{
-    header::{HeaderName, HeaderValue},
+    body::Bytes,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
     Body,
     Client,
+    Method,
     Request,
     Response,
     Server,
     StatusCode, // Use specific status code
     Uri,
 };
+use hyper::http::uri::{Authority, PathAndQuery};
 use hyper::service::{make_service_fn, service_fn};
-use std::{collections::HashMap, convert::Infallible, net::SocketAddr, sync::Arc, str};
-use serde::Serialize;
+use once_cell::sync::Lazy;
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    str,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{config, wasm_engine};
-#[derive(Serialize)]
-struct WasmInput {
-    method: String,
-    path: String,
-    headers: HashMap<String, String>,
-    body: String,
-}
-
+use crate::router::RouteRequest;
+use crate::wasm_engine::OverrideErrorKind;
 
 pub async fn start_proxy(config: Arc<config::Config>) {
     let make_svc = make_service_fn(move |_conn| {
@@ -57,44 +63,90 @@ fn simple_response(status: StatusCode, body: &'static str) -> Result<Response<Bo
         .unwrap())
 }
 
-async fn handle_request(
-    mut req: Request<Body>,
-    config: Arc<config::Config>,
-) -> Result<Response<Body>, Infallible> {
-    let path = req.uri().path().to_string();
-    let method = req.method().clone();
+/// An override and the settings it runs with.
+#[derive(Clone, Copy)]
+struct Stage<'a> {
+    file: &'a str,
+    opts: wasm_engine::RunOptions<'a>,
+    /// `override_config` JSON handed to the guest.
+    config: Option<&'a str>,
+    on_error: &'a config::OnOverrideError,
+    /// Reply with failure details (`dev_mode`).
+    dev_mode: bool,
+}
+
+impl<'a> Stage<'a> {
+    fn route(file: &'a str, route: &'a config::ProxyConfig, cfg: &config::Config, request_id: &'a str) -> Self {
+        let opts = wasm_engine::RunOptions::from(route).for_request(request_id);
+        let config = route.override_config.as_deref().map(|c| c.get());
+        Stage { file, opts, config, on_error: &route.on_override_error, dev_mode: cfg.dev_mode }
+    }
+
+    fn global(file: &'a str, cfg: &'a config::Config, request_id: &'a str) -> Self {
+        let opts = wasm_engine::RunOptions::from(cfg).for_request(request_id);
+        let config = cfg.global_override_config.as_deref().map(|c| c.get());
+        Stage { file, opts, config, on_error: &cfg.global_on_override_error, dev_mode: cfg.dev_mode }
+    }
+}
 
-    println!("➡️ Received request: {} {}", method, path);
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
-    let matched_proxy = config.proxies.iter().find(|p| {
-        match p.rule.r#type.as_str() {
-            "exact" => path == p.rule.path,
-            "contain" | _ => path.starts_with(&p.rule.path),
-        }
+/// Reuses the caller's `x-request-id`, or mints one that is unique per
+/// process run: `<start time hex>-<counter hex>`.
+fn request_id(headers: &HeaderMap) -> String {
+    static STARTED: Lazy<u64> = Lazy::new(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
     });
+    match headers.get("x-request-id").and_then(|v| v.to_str().ok()) {
+        Some(id) if !id.is_empty() && id.len() <= 128 => id.to_string(),
+        _ => format!("{:x}-{:x}", *STARTED, NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)),
+    }
+}
 
-    let proxy_config = match matched_proxy {
-        Some(p) => p,
-        None => {
-            println!("🚫 No matching proxy rule found for path: {}", path);
-            return simple_response(StatusCode::NOT_FOUND, "Not Found: No matching proxy rule.");
-        }
+/// Host name used for virtual host routing: the `Host` header, or the URI
+/// authority (HTTP/2 `:authority`) when there is none. The port is dropped.
+fn request_host(req: &Request<Body>) -> Option<String> {
+    let host = match req.headers().get(header::HOST) {
+        Some(value) => value.to_str().ok()?.parse::<Authority>().ok()?.host().to_string(),
+        None => req.uri().host()?.to_string(),
     };
+    Some(host)
+}
 
-    println!(
-        "✅ Matched rule for '{}' to app '{}' ({})",
-        path, proxy_config.app_name, proxy_config.app_uri
-    );
+/// Request state threaded through the override chains. Method and headers
+/// are edited on the request itself.
+struct ChainState<'a> {
+    /// Downstream path. Pre-routing `path` overrides are folded in before matching.
+    path: String,
+    /// Upstream path set by a route or post-routing override.
+    path_override: Option<String>,
+    app_url: Option<String>,
+    /// Set by an `on_override_error` fallback; wins over `app_url` and skips
+    /// the remaining overrides.
+    fallback: Option<String>,
+    query: Vec<(String, String)>,
+    query_edited: bool,
+    body: Bytes,
+    context: Vec<(String, String)>,
+    /// Named captures of the matched route's rule.
+    params: Vec<(String, String)>,
+    response_headers_to_add: Vec<(String, String)>,
+    response_headers_to_remove: Vec<String>,
+    /// Stages whose `modify-request` was called, one list per chain that
+    /// started. Only these get their `modify-response` hook.
+    ran: Vec<Vec<Stage<'a>>>,
+}
 
-    let mut target_uri_str = proxy_config.app_uri.clone(); // Base target
+async fn handle_request(
+    mut req: Request<Body>,
+    config: Arc<config::Config>,
+) -> Result<Response<Body>, Infallible> {
+    let path = req.uri().path().to_string();
+    let method = req.method().clone();
 
-    let headers_map: HashMap<String, String> = req
-        .headers()
-        .iter()
-        .filter_map(|(k, v)| {
-            v.to_str().ok().map(|v_str| (k.as_str().to_string(), v_str.to_string()))
-        })
-        .collect();
+    let request_id = request_id(req.headers());
+
+    println!("➡️ Received request: {} {} ({})", method, path, request_id);
 
     let body_bytes = match hyper::body::to_bytes(req.body_mut()).await {
         Ok(bytes) => bytes,
@@ -104,69 +156,109 @@ async fn handle_request(
         }
     };
 
-    let body_str = String::from_utf8_lossy(&body_bytes).to_string();
+    let mut state = ChainState {
+        path,
+        path_override: None,
+        app_url: None,
+        fallback: None,
+        query: req.uri().query()
+            .map(|q| form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+            .unwrap_or_default(),
+        query_edited: false,
+        body: body_bytes,
+        context: Vec::new(),
+        params: Vec::new(),
+        response_headers_to_add: Vec::new(),
+        response_headers_to_remove: Vec::new(),
+        ran: Vec::new(),
+    };
 
-    if let Some(wasm_file) = &proxy_config.override_file {
-        println!("⚙️ Running Wasm override: {}", wasm_file);
-        let wasm_input = WasmInput {
-            method: method.to_string(),
-            path: path.clone(),
-            headers: headers_map.clone(),
-            body: body_str.clone(),
-        };
+    let pre_routing: Vec<Stage> = config.pre_routing.iter().map(|f| Stage::global(f, &config, &request_id)).collect();
+    if let Some(res) = run_request_chain(&pre_routing, &mut req, &mut state).await {
+        return res;
+    }
+    // A pre-routing `path` rewrites the path used for route matching.
+    if let Some(new_path) = state.path_override.take() {
+        println!("↪️ Routing on rewritten path: {}", new_path);
+        state.path = new_path;
+    }
+    let path = state.path.clone();
+    let host = request_host(&req);
 
-        let input_json = match serde_json::to_string(&wasm_input) {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!("⚠️ Failed to serialize input for Wasm: {}", e);
-                return simple_response(StatusCode::INTERNAL_SERVER_ERROR, "Error preparing Wasm input.");
-            }
-        };
+    let route_request = RouteRequest {
+        host: host.as_deref(),
+        path: &path,
+        method: req.method(),
+        headers: req.headers(),
+        query: &state.query,
+    };
 
-        match wasm_engine::run_modify_request(wasm_file, &input_json).await {
-            Ok(out) => {
-                println!("✅ Wasm execution successful. Output: {:?}", out);
-                if let Some(new_target) = out.app_url {
-                    println!("↪️ Overriding target URI to: {}", new_target);
-                    target_uri_str = new_target;
-                }
+    // `None` when a pre-routing fallback takes a request no route matches.
+    let matched = match config.router.route(&route_request) {
+        Some(m) => {
+            state.params = m.params;
+            Some((&config.proxies[m.index], m.strip_len))
+        }
+        None if state.fallback.is_some() => {
+            println!("↪️ No matching proxy rule found for path: {}, using the pre-routing fallback", path);
+            None
+        }
+        None => {
+            println!("🚫 No matching proxy rule found for path: {} (host: {})", path, host.as_deref().unwrap_or("-"));
+            return simple_response(StatusCode::NOT_FOUND, "Not Found: No matching proxy rule.");
+        }
+    };
 
-                for (k, v) in out.headers_to_update {
-                    if let (Ok(name), Ok(value)) = (
-                        HeaderName::from_bytes(k.as_bytes()),
-                        HeaderValue::from_str(&v),
-                    ) {
-                        println!("Adding/Updating header: {} = {}", k, v);
-                        req.headers_mut().insert(name, value);
-                    } else {
-                        eprintln!("⚠️ Invalid header from Wasm: {} = {}", k, v);
-                    }
-                }
-                for k in out.headers_to_remove {
-                    if let Ok(name) = HeaderName::from_bytes(k.as_bytes()) {
-                        println!("Removing header: {}", k);
-                        req.headers_mut().remove(name);
-                    } else {
-                        eprintln!("⚠️ Invalid header name to remove from Wasm: {}", k);
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("❌ Wasm execution failed: {}", e);
-                return simple_response(StatusCode::INTERNAL_SERVER_ERROR, "Wasm override module failed.");
-            }
-        };
+    if let Some((proxy_config, _)) = matched {
+        println!(
+            "✅ Matched rule for '{}' to app '{}' ({})",
+            path, proxy_config.app_name, proxy_config.app_uri
+        );
+    }
+
+    let route: Vec<Stage> = match matched {
+        Some((proxy_config, _)) => proxy_config.override_chain().into_iter().map(|f| Stage::route(f, proxy_config, &config, &request_id)).collect(),
+        None => Vec::new(),
+    };
+    let post_routing: Vec<Stage> = config.post_routing.iter().map(|f| Stage::global(f, &config, &request_id)).collect();
+    for chain in [&route, &post_routing] {
+        if state.fallback.is_some() {
+            break;
+        }
+        if let Some(res) = run_request_chain(chain, &mut req, &mut state).await {
+            return res;
+        }
     }
 
-    let final_path_and_query = match proxy_config.rewrite.as_str() {
-        "strip" => {
-            req.uri().path_and_query()
-                .map(|pq| pq.as_str().strip_prefix(&proxy_config.rule.path).unwrap_or(pq.as_str()))
-                .unwrap_or("")
+    let final_path = match state.path_override {
+        Some(p) => p,
+        None => match matched {
+            Some((proxy_config, strip_len)) if proxy_config.rewrite == "strip" => path[strip_len..].to_string(),
+            _ => path.clone(),
         },
-        _ => req.uri().path_and_query().map(|pq| pq.as_str()).unwrap_or(""),
     };
 
+    // The original query string is passed through untouched (no re-encoding)
+    // unless an override edited it.
+    let final_query = if state.query_edited {
+        Some(
+            form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(state.query)
+                .finish(),
+        )
+    } else {
+        req.uri().query().map(str::to_string)
+    };
+
+    let final_path_and_query = match final_query {
+        Some(q) if !q.is_empty() => format!("{}?{}", final_path, q),
+        _ => final_path,
+    };
+
+    let target_uri_str = state.fallback
+        .or(state.app_url)
+        .or_else(|| matched.map(|(proxy_config, _)| proxy_config.app_uri.clone()))
+        .unwrap_or_default();
     let final_target_uri_str = format!(
         "{}{}",
         target_uri_str.trim_end_matches('/'),
@@ -184,18 +276,435 @@ async fn handle_request(
     println!("🚀 Forwarding request to: {}", final_uri);
 
     *req.uri_mut() = final_uri;
-    *req.body_mut() = Body::from(body_bytes); // Use original bytes
+    *req.body_mut() = Body::from(state.body); // Original bytes unless Wasm replaced them
 
     let client = Client::new();
 
     match client.request(req).await {
-        Ok(backend_res) => {
+        Ok(mut backend_res) => {
             println!("✅ Received response from backend: {}", backend_res.status());
-            Ok(backend_res)
+            apply_response_headers(&mut backend_res, state.response_headers_to_add, state.response_headers_to_remove);
+            if state.ran.iter().all(Vec::is_empty) {
+                Ok(backend_res)
+            } else {
+                let include_response_body = matched.is_some_and(|(proxy_config, _)| proxy_config.include_response_body);
+                run_response_overrides(&state.ran, include_response_body, &state.context, backend_res).await
+            }
         },
         Err(e) => {
             eprintln!("❌ Error forwarding request: {}", e);
             simple_response(StatusCode::BAD_GATEWAY, "Error connecting to upstream service.")
         }
     }
-}
\ No newline at end of file
+}
+
+/// Runs `modify-request` for each stage of `chain`; each sees the request as
+/// left by the previous one. Returns early with the response to send when an
+/// override short-circuits or fails. `stop` only ends this chain.
+async fn run_request_chain<'a>(
+    chain: &[Stage<'a>],
+    req: &mut Request<Body>,
+    state: &mut ChainState<'a>,
+) -> Option<Result<Response<Body>, Infallible>> {
+    state.ran.push(Vec::new());
+    for (idx, stage) in chain.iter().enumerate() {
+        let wasm_file = stage.file;
+        println!("⚙️ Running Wasm override {}/{}: {}", idx + 1, chain.len(), wasm_file);
+        let wasm_input = wasm_engine::WasmRequest {
+            method: req.method().to_string(),
+            path: state.path_override.clone().unwrap_or_else(|| state.path.clone()),
+            query: state.query.clone(),
+            headers: headers_to_list(req.headers()),
+            body: state.body.to_vec(),
+            context: state.context.clone(),
+            config: stage.config.map(str::to_string),
+            params: state.params.clone(),
+        };
+
+        if let Some(ran) = state.ran.last_mut() {
+            ran.push(*stage);
+        }
+        let outcome = match wasm_engine::run_modify_request(wasm_file, stage.opts, &wasm_input).await {
+            Ok(mut out) => {
+                println!("✅ Wasm execution successful. Output: {:?}", out);
+                match out.response.take().map(short_circuit_response) {
+                    Some(Ok(res)) => {
+                        println!("⏹️ Wasm returned a response, skipping upstream: {}", res.status());
+                        return Some(Ok(res));
+                    }
+                    Some(Err(e)) => Err((e, OverrideErrorKind::BadOutput)),
+                    None => match out.path.as_deref().map(check_upstream_path) {
+                        Some(Err(e)) => Err((e, OverrideErrorKind::BadOutput)),
+                        _ => Ok(out),
+                    },
+                }
+            }
+            Err(e) => {
+                let kind = OverrideErrorKind::of(&e);
+                Err((e, kind))
+            }
+        };
+        let out = match outcome {
+            Ok(out) => out,
+            Err((e, kind)) => match recover(stage, &e, kind, "Wasm override module failed.") {
+                Recovery::Respond(res) => return Some(res),
+                Recovery::Skip => continue,
+                Recovery::Fallback(app_uri) => {
+                    state.fallback = Some(app_uri);
+                    return None;
+                }
+            },
+        };
+
+        if let Some(new_target) = out.app_url {
+            println!("↪️ Overriding target URI to: {}", new_target);
+            state.app_url = Some(new_target);
+        }
+
+        if let Some(new_method) = out.method {
+            match Method::from_bytes(new_method.as_bytes()) {
+                Ok(m) => {
+                    println!("↪️ Overriding method to: {}", m);
+                    *req.method_mut() = m;
+                }
+                Err(_) => eprintln!("⚠️ Invalid method from Wasm: {}", new_method),
+            }
+        }
+
+        if let Some(new_path) = out.path {
+            println!("↪️ Overriding upstream path to: {}", new_path);
+            state.path_override = Some(new_path);
+        }
+        if !out.query_params_to_set.is_empty() || !out.query_params_to_remove.is_empty() {
+            edit_query(&mut state.query, out.query_params_to_set, &out.query_params_to_remove);
+            state.query_edited = true;
+        }
+
+        set_headers(req.headers_mut(), out.headers_to_update, "header");
+        for k in out.headers_to_remove {
+            if let Ok(name) = HeaderName::from_bytes(k.as_bytes()) {
+                println!("Removing header: {}", k);
+                req.headers_mut().remove(name);
+            } else {
+                eprintln!("⚠️ Invalid header name to remove from Wasm: {}", k);
+            }
+        }
+
+        if let Some(new_body) = out.body {
+            println!("↪️ Replacing request body ({} bytes)", new_body.len());
+            req.headers_mut().remove(header::TRANSFER_ENCODING);
+            req.headers_mut().insert(header::CONTENT_LENGTH, HeaderValue::from(new_body.len()));
+            state.body = new_body.into();
+        }
+
+        // A later stage's removal or addition replaces what earlier stages queued for that name.
+        for k in out.response_headers_to_remove {
+            state.response_headers_to_add.retain(|(added, _)| !added.eq_ignore_ascii_case(&k));
+            state.response_headers_to_remove.push(k);
+        }
+        state.response_headers_to_add.retain(|(added, _)| {
+            !out.response_headers_to_add.iter().any(|(k, _)| k.eq_ignore_ascii_case(added))
+        });
+        state.response_headers_to_add.extend(out.response_headers_to_add);
+
+        update_context(&mut state.context, out.context_to_set, &out.context_to_remove);
+
+        if out.stop {
+            println!("⏹️ Wasm override {} stopped the chain", wasm_file);
+            break;
+        }
+    }
+    None
+}
+
+/// What to do after an override failed, per its `on_override_error` policy.
+enum Recovery {
+    Respond(Result<Response<Body>, Infallible>),
+    Skip,
+    Fallback(String),
+}
+
+/// Counts and logs a failed override, then applies its stage's
+/// `on_override_error` policy.
+fn recover(stage: &Stage, err: &anyhow::Error, kind: OverrideErrorKind, default_body: &'static str) -> Recovery {
+    let total = kind.record();
+    let details = wasm_engine::FailureDetails::of(err);
+    log::error!(
+        target: "rilot::override",
+        app = stage.opts.app_name,
+        module = stage.file,
+        request_id = stage.opts.request_id,
+        kind:% = kind,
+        kind_total = total,
+        trap_code = details.trap_code.as_deref().unwrap_or("none");
+        "❌ Wasm override failed: {}", details.message
+    );
+    if let Some(backtrace) = &details.backtrace {
+        log::error!(
+            target: "rilot::override",
+            module = stage.file,
+            request_id = stage.opts.request_id;
+            "{}", backtrace
+        );
+    }
+
+    match stage.on_error {
+        config::OnOverrideError::FailClosed { status, body } => {
+            let (default_status, default_message) = wasm_error_status(err, kind, default_body);
+            let status = status
+                .and_then(|s| StatusCode::from_u16(s).ok())
+                .unwrap_or(default_status);
+            let body = if stage.dev_mode {
+                debug_body(stage, kind, &details)
+            } else {
+                body.clone().unwrap_or_else(|| default_message.to_string())
+            };
+            Recovery::Respond(Ok(Response::builder()
+                .status(status)
+                .header("Content-Type", "text/plain")
+                .body(Body::from(body))
+                .unwrap()))
+        }
+        config::OnOverrideError::FailOpen => {
+            println!("↩️ Failing open, ignoring output of {}", stage.file);
+            Recovery::Skip
+        }
+        config::OnOverrideError::Fallback { app_uri } => {
+            println!("↪️ Falling back to: {}", app_uri);
+            Recovery::Fallback(app_uri.clone())
+        }
+    }
+}
+
+/// Maps a Wasm failure to a status and message, keeping limit violations
+/// distinct from generic module errors.
+fn wasm_error_status(err: &anyhow::Error, kind: OverrideErrorKind, fallback: &'static str) -> (StatusCode, &'static str) {
+    match err.downcast_ref::<wasm_engine::LimitExceeded>() {
+        Some(wasm_engine::LimitExceeded::Fuel | wasm_engine::LimitExceeded::Timeout) => {
+            (StatusCode::GATEWAY_TIMEOUT, "Wasm override exceeded its execution limit.")
+        }
+        Some(wasm_engine::LimitExceeded::Memory) => {
+            (StatusCode::SERVICE_UNAVAILABLE, "Wasm override exceeded its memory limit.")
+        }
+        Some(wasm_engine::LimitExceeded::Output) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, "Wasm override exceeded its output limit.")
+        }
+        Some(wasm_engine::LimitExceeded::Concurrency) => {
+            (StatusCode::SERVICE_UNAVAILABLE, "Too many concurrent Wasm overrides, try again later.")
+        }
+        None if kind == OverrideErrorKind::BadOutput => {
+            (StatusCode::INTERNAL_SERVER_ERROR, "Wasm override returned invalid output.")
+        }
+        None => (StatusCode::INTERNAL_SERVER_ERROR, fallback),
+    }
+}
+
+/// `dev_mode` error body with everything the failure log has.
+fn debug_body(stage: &Stage, kind: OverrideErrorKind, details: &wasm_engine::FailureDetails) -> String {
+    let mut body = format!(
+        "Wasm override failed\napp: {}\nmodule: {}\nrequest_id: {}\nkind: {}\ntrap_code: {}\nerror: {}\n",
+        stage.opts.app_name,
+        stage.file,
+        stage.opts.request_id,
+        kind,
+        details.trap_code.as_deref().unwrap_or("none"),
+        details.message,
+    );
+    if let Some(backtrace) = &details.backtrace {
+        body.push_str(backtrace);
+    }
+    body
+}
+
+fn short_circuit_response(response: wasm_engine::WasmResponse) -> anyhow::Result<Response<Body>> {
+    let status = StatusCode::from_u16(response.status)
+        .map_err(|_| anyhow::anyhow!("Invalid response status from Wasm: {}", response.status))?;
+
+    let mut res = Response::new(Body::from(response.body));
+    *res.status_mut() = status;
+    apply_response_headers(&mut res, response.headers, Vec::new());
+    Ok(res)
+}
+
+/// A Wasm `path` is appended to the target URI, so anything but an
+/// absolute path could change the authority (`v2/x`, `@other.host/x`).
+fn check_upstream_path(path: &str) -> anyhow::Result<()> {
+    if !path.starts_with('/') {
+        anyhow::bail!("Invalid path from Wasm (must start with '/'): {}", path);
+    }
+    match path.parse::<PathAndQuery>() {
+        Ok(p) if p.query().is_none() && !path.contains('#') => Ok(()),
+        _ => anyhow::bail!("Invalid path from Wasm: {}", path),
+    }
+}
+
+/// Applies Wasm query edits to the decoded parameters.
+fn edit_query(
+    params: &mut Vec<(String, String)>,
+    params_to_set: Vec<(String, String)>,
+    params_to_remove: &[String],
+) {
+    params.retain(|(k, _)| !params_to_remove.contains(k));
+    for (k, v) in params_to_set {
+        println!("Setting query parameter: {} = {}", k, v);
+        match params.iter().position(|(existing, _)| *existing == k) {
+            Some(idx) => {
+                // Replace the first occurrence in place and drop any repeats.
+                let mut seen = 0;
+                params.retain(|(existing, _)| {
+                    let keep = *existing != k || seen == 0;
+                    if *existing == k {
+                        seen += 1;
+                    }
+                    keep
+                });
+                params[idx].1 = v;
+            }
+            None => params.push((k, v)),
+        }
+    }
+}
+
+/// Applies an override's context writes. Keys are unique; setting an
+/// existing key replaces its value.
+fn update_context(
+    context: &mut Vec<(String, String)>,
+    entries_to_set: Vec<(String, String)>,
+    keys_to_remove: &[String],
+) {
+    context.retain(|(k, _)| !keys_to_remove.contains(k));
+    for (k, v) in entries_to_set {
+        match context.iter_mut().find(|(existing, _)| *existing == k) {
+            Some(entry) => entry.1 = v,
+            None => context.push((k, v)),
+        }
+    }
+}
+
+fn headers_to_list(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(k, v)| {
+            v.to_str().ok().map(|v_str| (k.as_str().to_string(), v_str.to_string()))
+        })
+        .collect()
+}
+
+/// Runs the `modify-response` hooks of the stages that ran, innermost first:
+/// chains in reverse order and each chain's stages in reverse, so the first
+/// override to see the request has the last word on the response. `stop`
+/// only ends the current chain.
+async fn run_response_overrides(
+    chains: &[Vec<Stage<'_>>],
+    include_response_body: bool,
+    context: &[(String, String)],
+    mut res: Response<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let mut body_bytes = if include_response_body {
+        match hyper::body::to_bytes(res.body_mut()).await {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                eprintln!("⚠️ Failed to read upstream response body: {}", e);
+                return simple_response(StatusCode::BAD_GATEWAY, "Error reading upstream response body.");
+            }
+        }
+    } else {
+        None
+    };
+
+    for chain in chains.iter().rev() {
+        for stage in chain.iter().rev() {
+            let wasm_file = stage.file;
+            println!("⚙️ Running Wasm response override: {}", wasm_file);
+            let wasm_input = wasm_engine::UpstreamResponse {
+                status: res.status().as_u16(),
+                headers: headers_to_list(res.headers()),
+                body: body_bytes.as_ref().filter(|_| include_response_body).map(|b| b.to_vec()),
+                context: context.to_vec(),
+                config: stage.config.map(str::to_string),
+            };
+
+            let out = match wasm_engine::run_modify_response(wasm_file, stage.opts, &wasm_input).await {
+                Ok(Some(out)) => out,
+                Ok(None) => continue, // request-hook only
+                Err(e) => {
+                    let kind = OverrideErrorKind::of(&e);
+                    match recover(stage, &e, kind, "Wasm response override module failed.") {
+                        Recovery::Respond(res) => return res,
+                        // The upstream has already answered, so a fallback only skips this hook.
+                        Recovery::Skip | Recovery::Fallback(_) => continue,
+                    }
+                }
+            };
+            println!("✅ Wasm response override successful. Output: {:?}", out);
+
+            if let Some(status) = out.status {
+                match StatusCode::from_u16(status) {
+                    Ok(code) => {
+                        println!("↪️ Overriding response status to: {}", code);
+                        *res.status_mut() = code;
+                    }
+                    Err(_) => eprintln!("⚠️ Invalid response status from Wasm: {}", status),
+                }
+            }
+
+            apply_response_headers(&mut res, out.headers_to_update, out.headers_to_remove);
+
+            if let Some(new_body) = out.body {
+                println!("↪️ Replacing response body ({} bytes)", new_body.len());
+                res.headers_mut().remove(header::TRANSFER_ENCODING);
+                res.headers_mut().insert(header::CONTENT_LENGTH, HeaderValue::from(new_body.len()));
+                body_bytes = Some(new_body.into());
+            }
+
+            if out.stop {
+                println!("⏹️ Wasm response override {} stopped the chain", wasm_file);
+                break;
+            }
+        }
+    }
+
+    // Buffered or replaced bodies go back in; otherwise the upstream stream is untouched.
+    if let Some(bytes) = body_bytes {
+        *res.body_mut() = Body::from(bytes);
+    }
+
+    Ok(res)
+}
+
+fn apply_response_headers(
+    res: &mut Response<Body>,
+    headers_to_add: Vec<(String, String)>,
+    headers_to_remove: Vec<String>,
+) {
+    for k in headers_to_remove {
+        if let Ok(name) = HeaderName::from_bytes(k.as_bytes()) {
+            println!("Removing response header: {}", k);
+            res.headers_mut().remove(name);
+        } else {
+            eprintln!("⚠️ Invalid response header name to remove from Wasm: {}", k);
+        }
+    }
+    set_headers(res.headers_mut(), headers_to_add, "response header");
+}
+
+/// Replaces the values of every header named in `entries`. A name listed
+/// more than once keeps all of its values, e.g. several `set-cookie` entries.
+fn set_headers(headers: &mut HeaderMap, entries: Vec<(String, String)>, kind: &str) {
+    let mut replaced: Vec<HeaderName> = Vec::new();
+    for (k, v) in entries {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(k.as_bytes()),
+            HeaderValue::from_str(&v),
+        ) {
+            println!("Adding/Updating {}: {} = {}", kind, k, v);
+            if !replaced.contains(&name) {
+                headers.remove(&name);
+                replaced.push(name.clone());
+            }
+            headers.append(name, value);
+        } else {
+            eprintln!("⚠️ Invalid {} from Wasm: {} = {}", kind, k, v);
+        }
+    }
+}
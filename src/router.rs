@@ -0,0 +1,820 @@
+use anyhow::{anyhow, bail, Context, Result};
+use hyper::{
+    header::{self, HeaderMap, HeaderName},
+    Method,
+};
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+};
+
+use crate::config::{ProxyConfig, ProxyRule, ValuePredicate};
+
+/// Route rules compiled once at config load time. A request's virtual host
+/// is resolved first through hash lookups, then only that host's tables are
+/// searched, so a lookup costs O(path length) whatever the size of the
+/// route table.
+#[derive(Debug, Default)]
+pub struct Router {
+    /// In precedence order; the first match wins. Tables refer to routes by
+    /// their position here.
+    routes: Vec<CompiledRoute>,
+    /// Routes without a `host`, for hosts no route claims.
+    default: RouteTable,
+    exact_hosts: HashMap<String, RouteTable>,
+    /// Keyed by wildcard suffix, such as `.example.com`.
+    wildcard_hosts: HashMap<String, RouteTable>,
+    /// One table per distinct host regex; `host_patterns` matches in the same order.
+    host_patterns: RegexSet,
+    regex_hosts: Vec<RouteTable>,
+}
+
+/// The routes of one virtual host, indexed by rule kind.
+#[derive(Debug, Default)]
+struct RouteTable {
+    exact: HashMap<String, Vec<usize>>,
+    prefixes: PrefixTree,
+    /// `regex` and `glob` rules; `pattern_routes[i]` is the route of pattern `i`.
+    patterns: RegexSet,
+    pattern_routes: Vec<usize>,
+}
+
+/// Radix tree over prefix rules: walking a path visits every prefix rule
+/// that matches it.
+#[derive(Debug, Default)]
+struct PrefixTree {
+    /// Routes whose prefix ends at this node.
+    routes: Vec<usize>,
+    /// Edges sorted by label; no two labels share a first byte.
+    children: Vec<(Vec<u8>, PrefixTree)>,
+}
+
+#[derive(Debug)]
+struct CompiledRoute {
+    /// Index into `Config::proxies`.
+    index: usize,
+    priority: i32,
+    host: Option<HostMatcher>,
+    matcher: PathMatcher,
+    predicates: Predicates,
+}
+
+/// The parts of a request that routing looks at.
+pub struct RouteRequest<'a> {
+    pub host: Option<&'a str>,
+    pub path: &'a str,
+    pub method: &'a Method,
+    pub headers: &'a HeaderMap,
+    pub query: &'a [(String, String)],
+}
+
+/// Conditions besides host and path; all of them must hold.
+#[derive(Debug)]
+struct Predicates {
+    methods: Vec<Method>,
+    headers: Vec<(HeaderName, ValueTest)>,
+    query: Vec<(String, ValueTest)>,
+    cookies: Vec<(String, ValueTest)>,
+}
+
+#[derive(Debug)]
+enum ValueTest {
+    Present,
+    Absent,
+    Equals(String),
+    Regex(Regex),
+}
+
+/// Host names are matched case-insensitively, without the port.
+#[derive(Debug)]
+enum HostMatcher {
+    Exact(String),
+    /// `*.example.com`, stored as `.example.com`; matches any subdomain.
+    Wildcard(String),
+    Regex(Regex),
+}
+
+#[derive(Debug)]
+enum PathMatcher {
+    Exact(String),
+    Prefix(String),
+    Regex(Regex),
+    /// A glob translated to an anchored regex. `rewrite: "strip"` removes the
+    /// glob's leading literal directories (`literal_len` bytes).
+    Glob { regex: Regex, literal_len: usize },
+}
+
+/// The route chosen for a request.
+#[derive(Debug)]
+pub struct RouteMatch {
+    /// Index into `Config::proxies`.
+    pub index: usize,
+    /// Named capture groups of a `regex` rule or `{name}` segments of a `glob` rule.
+    pub params: Vec<(String, String)>,
+    /// Length of the leading part of the path removed by `rewrite: "strip"`.
+    pub strip_len: usize,
+}
+
+impl Router {
+    /// Compiles every route's rule, rejecting unknown rule types and invalid
+    /// patterns, and orders the routes by precedence. Routes that can never
+    /// match are logged as warnings.
+    pub fn new(proxies: &[ProxyConfig]) -> Result<Self> {
+        let mut routes = proxies
+            .iter()
+            .enumerate()
+            .map(|(index, proxy)| {
+                let context = || format!("Route '{}' ({})", proxy.app_name, proxy.rule.path);
+                let host = HostMatcher::compile(&proxy.rule).with_context(context)?;
+                let matcher = PathMatcher::compile(&proxy.rule).with_context(context)?;
+                let predicates = Predicates::compile(&proxy.rule).with_context(context)?;
+                Ok(CompiledRoute { index, priority: proxy.rule.priority, host, matcher, predicates })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Stable, so equally ranked routes keep their config order.
+        routes.sort_by_key(|route| (Reverse(route.priority), route.matcher.rank()));
+
+        let mut router = Router::default();
+        let mut host_patterns: Vec<&str> = Vec::new();
+        for (pos, route) in routes.iter().enumerate() {
+            let table = match &route.host {
+                None => &mut router.default,
+                Some(HostMatcher::Exact(h)) => router.exact_hosts.entry(h.clone()).or_default(),
+                Some(HostMatcher::Wildcard(suffix)) => router.wildcard_hosts.entry(suffix.clone()).or_default(),
+                Some(HostMatcher::Regex(re)) => {
+                    let idx = match host_patterns.iter().position(|p| *p == re.as_str()) {
+                        Some(idx) => idx,
+                        None => {
+                            host_patterns.push(re.as_str());
+                            router.regex_hosts.push(RouteTable::default());
+                            host_patterns.len() - 1
+                        }
+                    };
+                    &mut router.regex_hosts[idx]
+                }
+            };
+            table.insert(pos, &route.matcher);
+        }
+        router.host_patterns = RegexSetBuilder::new(host_patterns)
+            .case_insensitive(true)
+            .build()
+            .context("Failed to compile the host regexes")?;
+        let tables = std::iter::once(&mut router.default)
+            .chain(router.exact_hosts.values_mut())
+            .chain(router.wildcard_hosts.values_mut())
+            .chain(router.regex_hosts.iter_mut());
+        for table in tables {
+            table.compile_patterns(&routes)?;
+        }
+
+        router.routes = routes;
+        for (shadowed, by) in router.unreachable_routes() {
+            let (shadowed, by) = (&proxies[shadowed], &proxies[by]);
+            log::warn!(
+                "⚠️ Route '{}' ({}) can never match: route '{}' ({}) takes precedence",
+                shadowed.app_name, shadowed.rule.path, by.app_name, by.rule.path
+            );
+        }
+        Ok(router)
+    }
+
+    /// First route, in precedence order, whose rule matches the request. Like a
+    /// virtual host, a `host` that some route claims only sees the routes
+    /// for that host; any other host sees the routes without one.
+    pub fn route(&self, req: &RouteRequest) -> Option<RouteMatch> {
+        let host = req.host.map(str::to_ascii_lowercase);
+        let mut tables = host.as_deref().map(|h| self.host_tables(h)).unwrap_or_default();
+        if tables.is_empty() {
+            tables.push(&self.default);
+        }
+        let mut candidates = Vec::new();
+        for table in tables {
+            table.path_candidates(req.path, &mut candidates);
+        }
+        candidates.sort_unstable();
+        candidates
+            .into_iter()
+            .map(|pos| &self.routes[pos])
+            .filter_map(|route| route.matcher.matches(req.path, route.index).map(|m| (route, m)))
+            .find(|(route, _)| route.predicates.matches(req))
+            .map(|(_, m)| m)
+    }
+
+    /// Tables of the routes whose `host` matches `host`, which is already
+    /// lowercase. Empty when no route claims the host.
+    fn host_tables(&self, host: &str) -> Vec<&RouteTable> {
+        let mut tables: Vec<&RouteTable> = self.exact_hosts.get(host).into_iter().collect();
+        // `*.example.com` matches any name ending in `.example.com`, but not `example.com`.
+        tables.extend(
+            host.match_indices('.')
+                .filter(|(i, _)| *i > 0)
+                .filter_map(|(i, _)| self.wildcard_hosts.get(&host[i..])),
+        );
+        if !self.regex_hosts.is_empty() {
+            tables.extend(self.host_patterns.matches(host).into_iter().map(|i| &self.regex_hosts[i]));
+        }
+        tables
+    }
+
+    /// Tables that could serve a request for `route`'s host.
+    fn tables_serving(&self, route: &CompiledRoute) -> Vec<&RouteTable> {
+        match &route.host {
+            None => vec![&self.default],
+            Some(HostMatcher::Exact(h)) => self.host_tables(h),
+            Some(HostMatcher::Wildcard(suffix)) => suffix
+                .match_indices('.')
+                .filter_map(|(i, _)| self.wildcard_hosts.get(&suffix[i..]))
+                .collect(),
+            Some(HostMatcher::Regex(re)) => self
+                .host_patterns
+                .patterns()
+                .iter()
+                .position(|p| p == re.as_str())
+                .map(|i| vec![&self.regex_hosts[i]])
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Routes that can never match because a route with higher precedence
+    /// takes every request they would get, as `(shadowed, by)` indexes into
+    /// `Config::proxies`.
+    fn unreachable_routes(&self) -> Vec<(usize, usize)> {
+        let mut unreachable = Vec::new();
+        for (pos, route) in self.routes.iter().enumerate() {
+            // Only routes that match every path of `route` can shadow it.
+            let mut candidates = Vec::new();
+            for table in self.tables_serving(route) {
+                match &route.matcher {
+                    PathMatcher::Exact(p) => table.path_candidates(p, &mut candidates),
+                    PathMatcher::Prefix(p) => table.prefixes.collect(p.as_bytes(), &mut candidates),
+                    PathMatcher::Regex(_) | PathMatcher::Glob { .. } => {
+                        candidates.extend_from_slice(&table.pattern_routes);
+                        table.prefixes.collect(b"/", &mut candidates);
+                    }
+                }
+            }
+            let shadowing = candidates
+                .into_iter()
+                .filter(|&earlier| earlier < pos && self.routes[earlier].shadows(route))
+                .min();
+            if let Some(earlier) = shadowing {
+                unreachable.push((route.index, self.routes[earlier].index));
+            }
+        }
+        unreachable
+    }
+}
+
+impl RouteTable {
+    fn insert(&mut self, pos: usize, matcher: &PathMatcher) {
+        match matcher {
+            PathMatcher::Exact(p) => self.exact.entry(p.clone()).or_default().push(pos),
+            PathMatcher::Prefix(p) => self.prefixes.insert(p.as_bytes(), pos),
+            PathMatcher::Regex(_) | PathMatcher::Glob { .. } => self.pattern_routes.push(pos),
+        }
+    }
+
+    /// Builds the `RegexSet` once every pattern route is inserted.
+    fn compile_patterns(&mut self, routes: &[CompiledRoute]) -> Result<()> {
+        let patterns = self.pattern_routes.iter().map(|&pos| match &routes[pos].matcher {
+            PathMatcher::Regex(re) | PathMatcher::Glob { regex: re, .. } => re.as_str(),
+            _ => unreachable!("only pattern rules are in pattern_routes"),
+        });
+        self.patterns = RegexSet::new(patterns).context("Failed to compile the regex and glob rules")?;
+        Ok(())
+    }
+
+    /// Appends the positions of the routes whose path rule matches `path`.
+    fn path_candidates(&self, path: &str, out: &mut Vec<usize>) {
+        if let Some(exact) = self.exact.get(path) {
+            out.extend_from_slice(exact);
+        }
+        self.prefixes.collect(path.as_bytes(), out);
+        if !self.pattern_routes.is_empty() {
+            out.extend(self.patterns.matches(path).into_iter().map(|i| self.pattern_routes[i]));
+        }
+    }
+}
+
+impl PrefixTree {
+    fn insert(&mut self, key: &[u8], pos: usize) {
+        if key.is_empty() {
+            self.routes.push(pos);
+            return;
+        }
+        let idx = match self.children.binary_search_by_key(&key[0], |(label, _)| label[0]) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                self.children.insert(idx, (key.to_vec(), PrefixTree { routes: vec![pos], children: Vec::new() }));
+                return;
+            }
+        };
+        let (label, child) = &mut self.children[idx];
+        let common = label.iter().zip(key).take_while(|(a, b)| a == b).count();
+        if common < label.len() {
+            // Split the edge at the end of the shared part.
+            let tail = label.split_off(common);
+            let old = std::mem::take(child);
+            child.children.push((tail, old));
+        }
+        child.insert(&key[common..], pos);
+    }
+
+    /// Appends the routes of every prefix of `path` in the tree.
+    fn collect(&self, mut path: &[u8], out: &mut Vec<usize>) {
+        let mut node = self;
+        loop {
+            out.extend_from_slice(&node.routes);
+            let Some(&first) = path.first() else { return };
+            let Ok(idx) = node.children.binary_search_by_key(&first, |(label, _)| label[0]) else { return };
+            let (label, child) = &node.children[idx];
+            if !path.starts_with(label) {
+                return;
+            }
+            path = &path[label.len()..];
+            node = child;
+        }
+    }
+}
+
+impl CompiledRoute {
+    /// Whether every request `later` could match is taken by `self` first.
+    /// Conservative: only catches duplicates and catch-all rules.
+    fn shadows(&self, later: &CompiledRoute) -> bool {
+        let host_covered = match (&self.host, &later.host) {
+            (None, None) => true,
+            (Some(h), Some(l)) => h.covers(l),
+            _ => false,
+        };
+        host_covered && self.predicates.covers(&later.predicates) && self.matcher.covers(&later.matcher)
+    }
+}
+
+impl Predicates {
+    /// Whether any request meeting `later`'s conditions meets these too.
+    fn covers(&self, later: &Predicates) -> bool {
+        let methods_covered = self.methods.is_empty()
+            || (!later.methods.is_empty() && later.methods.iter().all(|m| self.methods.contains(m)));
+        methods_covered && self.headers.is_empty() && self.query.is_empty() && self.cookies.is_empty()
+    }
+
+    fn compile(rule: &ProxyRule) -> Result<Self> {
+        let methods = rule
+            .methods
+            .iter()
+            .map(|m| {
+                Method::from_bytes(m.to_ascii_uppercase().as_bytes()).map_err(|_| anyhow!("invalid method '{}'", m))
+            })
+            .collect::<Result<_>>()?;
+        let headers = rule
+            .headers
+            .iter()
+            .map(|p| {
+                let name = HeaderName::from_bytes(p.name.as_bytes())
+                    .map_err(|_| anyhow!("invalid header name '{}'", p.name))?;
+                Ok((name, ValueTest::compile(p).with_context(|| format!("header '{}'", p.name))?))
+            })
+            .collect::<Result<_>>()?;
+        let named = |predicates: &[ValuePredicate], what: &str| {
+            predicates
+                .iter()
+                .map(|p| Ok((p.name.clone(), ValueTest::compile(p).with_context(|| format!("{} '{}'", what, p.name))?)))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Predicates { methods, headers, query: named(&rule.query, "query parameter")?, cookies: named(&rule.cookies, "cookie")? })
+    }
+
+    fn matches(&self, req: &RouteRequest) -> bool {
+        if !self.methods.is_empty() && !self.methods.contains(req.method) {
+            return false;
+        }
+        let headers_hold = self.headers.iter().all(|(name, test)| {
+            test.holds(req.headers.get_all(name).iter().filter_map(|v| v.to_str().ok()))
+        });
+        let query_holds = self.query.iter().all(|(name, test)| {
+            test.holds(req.query.iter().filter(|(k, _)| k == name).map(|(_, v)| v.as_str()))
+        });
+        headers_hold && query_holds && (self.cookies.is_empty() || self.cookies_hold(req.headers))
+    }
+
+    fn cookies_hold(&self, headers: &HeaderMap) -> bool {
+        let cookies: Vec<(&str, &str)> = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .collect();
+        self.cookies.iter().all(|(name, test)| {
+            test.holds(cookies.iter().filter(|(k, _)| k == name).map(|(_, v)| *v))
+        })
+    }
+}
+
+impl ValueTest {
+    fn compile(predicate: &ValuePredicate) -> Result<Self> {
+        match (&predicate.equals, &predicate.regex, predicate.present) {
+            (Some(_), Some(_), _) => bail!("set only one of `equals` and `regex`"),
+            (Some(_), _, Some(false)) | (_, Some(_), Some(false)) => bail!("`present: false` cannot be combined with a value"),
+            (Some(value), None, _) => Ok(ValueTest::Equals(value.clone())),
+            (None, Some(re), _) => Regex::new(re).map(ValueTest::Regex).map_err(|e| anyhow!("invalid regex: {}", e)),
+            (None, None, Some(false)) => Ok(ValueTest::Absent),
+            (None, None, _) => Ok(ValueTest::Present),
+        }
+    }
+
+    /// Whether any of the request's `values` for the name satisfies the test.
+    fn holds<'v>(&self, mut values: impl Iterator<Item = &'v str>) -> bool {
+        match self {
+            ValueTest::Present => values.next().is_some(),
+            ValueTest::Absent => values.next().is_none(),
+            ValueTest::Equals(expected) => values.any(|v| v == expected),
+            ValueTest::Regex(re) => values.any(|v| re.is_match(v)),
+        }
+    }
+}
+
+impl HostMatcher {
+    fn covers(&self, later: &HostMatcher) -> bool {
+        match (self, later) {
+            (HostMatcher::Exact(a), HostMatcher::Exact(b)) => a == b,
+            (HostMatcher::Wildcard(a), HostMatcher::Wildcard(b)) => b.ends_with(a.as_str()),
+            (HostMatcher::Wildcard(_), HostMatcher::Exact(b)) => self.matches(b),
+            (HostMatcher::Regex(a), HostMatcher::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+
+    fn compile(rule: &ProxyRule) -> Result<Option<Self>> {
+        let Some(host) = &rule.host else { return Ok(None) };
+        let host_type = match rule.host_type.as_deref() {
+            Some(t) => t,
+            None if host.starts_with("*.") => "wildcard",
+            None => "exact",
+        };
+        let matcher = match host_type {
+            "exact" => HostMatcher::Exact(host.to_ascii_lowercase()),
+            "wildcard" => match host.strip_prefix('*') {
+                Some(suffix) if suffix.starts_with('.') => HostMatcher::Wildcard(suffix.to_ascii_lowercase()),
+                _ => bail!("wildcard host '{}' must start with '*.'", host),
+            },
+            "regex" => RegexBuilder::new(host)
+                .case_insensitive(true)
+                .build()
+                .map(HostMatcher::Regex)
+                .map_err(|e| anyhow!("invalid host regex: {}", e))?,
+            other => bail!("unknown host type '{}' (expected exact, wildcard or regex)", other),
+        };
+        Ok(Some(matcher))
+    }
+
+    /// `host` is already lowercase.
+    fn matches(&self, host: &str) -> bool {
+        match self {
+            HostMatcher::Exact(h) => host == h,
+            HostMatcher::Wildcard(suffix) => host.len() > suffix.len() && host.ends_with(suffix.as_str()),
+            HostMatcher::Regex(re) => re.is_match(host),
+        }
+    }
+}
+
+impl PathMatcher {
+    /// Sort key within a priority: exact, then pattern rules, then prefixes
+    /// from longest to shortest.
+    fn rank(&self) -> (u8, Reverse<usize>) {
+        match self {
+            PathMatcher::Exact(_) => (0, Reverse(0)),
+            PathMatcher::Regex(_) | PathMatcher::Glob { .. } => (1, Reverse(0)),
+            PathMatcher::Prefix(p) => (2, Reverse(p.len())),
+        }
+    }
+
+    /// Whether every path `later` matches is matched by `self`.
+    fn covers(&self, later: &PathMatcher) -> bool {
+        match (self, later) {
+            // Request paths always start with `/`.
+            (PathMatcher::Prefix(p), _) if p.is_empty() || p == "/" => true,
+            (PathMatcher::Prefix(p), PathMatcher::Prefix(q) | PathMatcher::Exact(q)) => q.starts_with(p.as_str()),
+            (PathMatcher::Exact(p), PathMatcher::Exact(q)) => p == q,
+            (PathMatcher::Regex(a), PathMatcher::Regex(b)) => a.as_str() == b.as_str(),
+            (PathMatcher::Glob { regex: a, .. }, PathMatcher::Glob { regex: b, .. }) => a.as_str() == b.as_str(),
+            (PathMatcher::Regex(re) | PathMatcher::Glob { regex: re, .. }, PathMatcher::Exact(q)) => re.is_match(q),
+            _ => false,
+        }
+    }
+
+    fn compile(rule: &ProxyRule) -> Result<Self> {
+        match rule.r#type.as_str() {
+            "exact" => Ok(PathMatcher::Exact(rule.path.clone())),
+            "prefix" | "contain" => Ok(PathMatcher::Prefix(rule.path.clone())),
+            "regex" => Regex::new(&rule.path)
+                .map(PathMatcher::Regex)
+                .map_err(|e| anyhow!("invalid regex rule: {}", e)),
+            "glob" => {
+                let regex = Regex::new(&glob_to_regex(&rule.path)?)
+                    .map_err(|e| anyhow!("invalid glob rule: {}", e))?;
+                let literal = &rule.path[..rule.path.find(['*', '?', '{']).unwrap_or(rule.path.len())];
+                // Keep the final `/` so the stripped path stays absolute.
+                let literal_len = literal.rfind('/').unwrap_or(0);
+                Ok(PathMatcher::Glob { regex, literal_len })
+            }
+            other => bail!("unknown rule type '{}' (expected exact, prefix, contain, regex or glob)", other),
+        }
+    }
+
+    fn matches(&self, path: &str, index: usize) -> Option<RouteMatch> {
+        let found = |params, strip_len| Some(RouteMatch { index, params, strip_len });
+        match self {
+            PathMatcher::Exact(p) if path == p => found(Vec::new(), p.len()),
+            PathMatcher::Prefix(p) if path.starts_with(p.as_str()) => found(Vec::new(), keep_slash(path, p.len())),
+            PathMatcher::Regex(re) | PathMatcher::Glob { regex: re, .. } => {
+                let caps = re.captures(path)?;
+                let params = re
+                    .capture_names()
+                    .flatten()
+                    .filter_map(|name| caps.name(name).map(|m| (name.to_string(), m.as_str().to_string())))
+                    .collect();
+                let strip_len = match self {
+                    PathMatcher::Glob { literal_len, .. } => *literal_len,
+                    // Only a match anchored at the start of the path can be stripped.
+                    _ => caps
+                        .get(0)
+                        .filter(|whole| whole.start() == 0)
+                        .map_or(0, |whole| keep_slash(path, whole.end())),
+                };
+                found(params, strip_len)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Backs `strip_len` up to the last `/` it covers when stripping it would
+/// leave a remainder that is not an absolute path, as globs already do.
+fn keep_slash(path: &str, strip_len: usize) -> usize {
+    let rest = &path[strip_len..];
+    if rest.is_empty() || rest.starts_with('/') {
+        strip_len
+    } else {
+        path[..strip_len].rfind('/').unwrap_or(0)
+    }
+}
+
+/// `*` matches within one path segment, `**` across segments (`/a/**/b`
+/// also matches `/a/b`), `?` one character other than `/`, and `{name}` one
+/// non-empty segment captured as `name`. The glob must match the whole path.
+fn glob_to_regex(glob: &str) -> Result<String> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.next_if_eq(&'/').is_some() {
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => bail!("unterminated glob segment '{{{}'", name),
+                    }
+                }
+                let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid {
+                    bail!("invalid glob segment name '{{{}}}'", name);
+                }
+                pattern.push_str(&format!("(?P<{}>[^/]+)", name));
+            }
+            _ => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Ok(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Builds a router with one route per rule; route `i` is named `r{i}`.
+    fn build_router(rules: serde_json::Value) -> Router {
+        let proxies: Vec<ProxyConfig> = rules
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                serde_json::from_value(json!({ "app_name": format!("r{}", i), "app_uri": "http://up", "rule": rule }))
+                    .unwrap()
+            })
+            .collect();
+        Router::new(&proxies).unwrap()
+    }
+
+    fn route(router: &Router, host: Option<&str>, path: &str) -> Option<RouteMatch> {
+        let req = RouteRequest { host, path, method: &Method::GET, headers: &HeaderMap::new(), query: &[] };
+        router.route(&req)
+    }
+
+    fn index(router: &Router, host: Option<&str>, path: &str) -> Option<usize> {
+        route(router, host, path).map(|m| m.index)
+    }
+
+    fn collect(tree: &PrefixTree, path: &str) -> Vec<usize> {
+        let mut out = Vec::new();
+        tree.collect(path.as_bytes(), &mut out);
+        out
+    }
+
+    #[test]
+    fn prefix_tree_splits_edges() {
+        let mut tree = PrefixTree::default();
+        tree.insert(b"/api", 0);
+        tree.insert(b"/apple", 1);
+        tree.insert(b"/ap", 2);
+
+        // `/api` and `/apple` share `/ap`, which then gets its own route.
+        assert_eq!(tree.children.len(), 1);
+        let (label, node) = &tree.children[0];
+        assert_eq!(label, b"/ap");
+        assert_eq!(node.routes, vec![2]);
+        let labels: Vec<&[u8]> = node.children.iter().map(|(l, _)| l.as_slice()).collect();
+        assert_eq!(labels, vec![b"i".as_slice(), b"ple".as_slice()]);
+
+        assert_eq!(collect(&tree, "/api/users"), vec![2, 0]);
+        assert_eq!(collect(&tree, "/apple"), vec![2, 1]);
+        assert_eq!(collect(&tree, "/apricot"), vec![2]);
+        assert_eq!(collect(&tree, "/a"), Vec::<usize>::new());
+        assert_eq!(collect(&tree, "/other"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn prefix_tree_empty_and_duplicate_keys() {
+        let mut tree = PrefixTree::default();
+        tree.insert(b"", 0);
+        tree.insert(b"/a", 1);
+        tree.insert(b"/a", 2);
+        assert_eq!(collect(&tree, "/a/b"), vec![0, 1, 2]);
+        assert_eq!(collect(&tree, "/b"), vec![0]);
+    }
+
+    #[test]
+    fn exact_beats_regex_beats_longest_prefix() {
+        let router = build_router(json!([
+            { "path": "/" },
+            { "path": "/api" },
+            { "path": "/api/v2" },
+            { "path": "^/api/v2/users/[0-9]+$", "type": "regex" },
+            { "path": "/api/v2/users/1", "type": "exact" },
+        ]));
+        assert_eq!(index(&router, None, "/api/v2/users/1"), Some(4));
+        assert_eq!(index(&router, None, "/api/v2/users/7"), Some(3));
+        assert_eq!(index(&router, None, "/api/v2/other"), Some(2));
+        assert_eq!(index(&router, None, "/api/v1"), Some(1));
+        assert_eq!(index(&router, None, "/x"), Some(0));
+    }
+
+    #[test]
+    fn priority_overrides_rule_kind() {
+        let router = build_router(json!([
+            { "path": "/api/1", "type": "exact" },
+            { "path": "/api", "priority": 10 },
+            { "path": "/", "priority": -1 },
+            { "path": "/other" },
+        ]));
+        assert_eq!(index(&router, None, "/api/1"), Some(1));
+        assert_eq!(index(&router, None, "/other"), Some(3));
+        assert_eq!(index(&router, None, "/x"), Some(2));
+    }
+
+    #[test]
+    fn equal_rank_keeps_config_order() {
+        let router = build_router(json!([
+            { "path": "^/a", "type": "regex" },
+            { "path": "^/a/b", "type": "regex" },
+        ]));
+        assert_eq!(index(&router, None, "/a/b"), Some(0));
+    }
+
+    #[test]
+    fn hosts_are_claimed_by_exact_wildcard_and_regex_rules() {
+        let router = build_router(json!([
+            { "path": "/", "host": "api.example.com" },
+            { "path": "/", "host": "*.example.com" },
+            { "path": "/", "host": "^node-[0-9]+\\.internal$", "host_type": "regex" },
+            { "path": "/default" },
+        ]));
+        assert_eq!(index(&router, Some("api.example.com"), "/x"), Some(0));
+        assert_eq!(index(&router, Some("API.Example.COM"), "/x"), Some(0));
+        assert_eq!(index(&router, Some("a.example.com"), "/x"), Some(1));
+        assert_eq!(index(&router, Some("a.b.example.com"), "/x"), Some(1));
+        assert_eq!(index(&router, Some("node-3.internal"), "/x"), Some(2));
+        // Unclaimed hosts, or no host at all, get the default routes.
+        assert_eq!(index(&router, Some("example.com"), "/default"), Some(3));
+        assert_eq!(index(&router, Some("node-x.internal"), "/default"), Some(3));
+        assert_eq!(index(&router, None, "/default"), Some(3));
+        assert_eq!(index(&router, Some("example.com"), "/x"), None);
+    }
+
+    #[test]
+    fn claimed_hosts_do_not_fall_back_to_default_routes() {
+        let router = build_router(json!([
+            { "path": "/app", "host": "*.example.com" },
+            { "path": "/" },
+        ]));
+        assert_eq!(index(&router, Some("a.example.com"), "/app"), Some(0));
+        assert_eq!(index(&router, Some("a.example.com"), "/other"), None);
+        assert_eq!(index(&router, Some("elsewhere.org"), "/other"), Some(1));
+    }
+
+    #[test]
+    fn glob_strip_lengths() {
+        let strip = |glob: &str, path: &str| {
+            let router = build_router(json!([{ "path": glob, "type": "glob" }]));
+            route(&router, None, path).map(|m| m.strip_len)
+        };
+        assert_eq!(strip("/assets/*/img/**.png", "/assets/a/img/x/y.png"), Some("/assets".len()));
+        assert_eq!(strip("/static/**", "/static/css/site.css"), Some("/static".len()));
+        assert_eq!(strip("/{tenant}/home", "/acme/home"), Some(0));
+        assert_eq!(strip("/files/v?/*", "/files/v2/a"), Some("/files".len()));
+        assert_eq!(strip("/a/b.txt", "/a/b.txt"), Some("/a".len()));
+    }
+
+    #[test]
+    fn glob_syntax() {
+        let router = build_router(json!([{ "path": "/a/**/b/*.txt", "type": "glob" }]));
+        assert!(route(&router, None, "/a/b/x.txt").is_some());
+        assert!(route(&router, None, "/a/x/y/b/z.txt").is_some());
+        assert!(route(&router, None, "/a/b/x/y.txt").is_none());
+        assert!(route(&router, None, "/ab/x.txt").is_none());
+
+        let router = build_router(json!([{ "path": "/shop/{tenant}/items/{id}", "type": "glob" }]));
+        let m = route(&router, None, "/shop/acme/items/42").unwrap();
+        assert_eq!(m.params, vec![("tenant".to_string(), "acme".to_string()), ("id".to_string(), "42".to_string())]);
+        assert!(route(&router, None, "/shop/acme/items/").is_none());
+
+        assert_eq!(glob_to_regex("/a.b?").unwrap(), "^/a\\.b[^/]$");
+        assert!(glob_to_regex("/{bad-name}").is_err());
+        assert!(glob_to_regex("/{}").is_err());
+        assert!(glob_to_regex("/{id").is_err());
+        assert!(glob_to_regex("/{").is_err());
+    }
+
+    #[test]
+    fn regex_params_and_strip() {
+        let router = build_router(json!([{ "path": "^/v(?P<version>[0-9]+)", "type": "regex" }]));
+        let m = route(&router, None, "/v2/users").unwrap();
+        assert_eq!(m.params, vec![("version".to_string(), "2".to_string())]);
+        assert_eq!(m.strip_len, "/v2".len());
+
+        let router = build_router(json!([{ "path": "^/api/", "type": "regex" }]));
+        let m = route(&router, None, "/api/users").unwrap();
+        assert_eq!(&"/api/users"[m.strip_len..], "/users");
+
+        let router = build_router(json!([{ "path": "/api", "type": "prefix" }]));
+        assert_eq!(route(&router, None, "/api/users").unwrap().strip_len, "/api".len());
+        assert_eq!(route(&router, None, "/apiary").unwrap().strip_len, 0);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let build = |rule: serde_json::Value| {
+            let proxy: ProxyConfig =
+                serde_json::from_value(json!({ "app_name": "a", "app_uri": "http://up", "rule": rule })).unwrap();
+            Router::new(&[proxy])
+        };
+        assert!(build(json!({ "path": "/", "type": "contains" })).is_err());
+        assert!(build(json!({ "path": "(", "type": "regex" })).is_err());
+        assert!(build(json!({ "path": "/", "host": "example.*", "host_type": "wildcard" })).is_err());
+        assert!(build(json!({ "path": "/", "host_type": "dns", "host": "a" })).is_err());
+    }
+
+    #[test]
+    fn unreachable_routes_are_reported() {
+        let router = build_router(json!([
+            { "path": "/" },
+            { "path": "/api/1", "type": "exact" },
+            { "path": "/api/1", "type": "exact" },
+            { "path": "/promo", "priority": 10 },
+            { "path": "/promo/x", "type": "exact" },
+            { "path": "/", "priority": -1, "methods": ["GET"] },
+            { "path": "/", "host": "a.example.com" },
+            { "path": "/", "host": "*.example.com", "priority": 1 },
+            { "path": "/beta", "headers": [{ "name": "x-beta", "equals": "1" }] },
+        ]));
+        let mut unreachable = router.unreachable_routes();
+        unreachable.sort_unstable();
+        assert_eq!(unreachable, vec![(2, 1), (4, 3), (5, 0), (6, 7)]);
+    }
+}
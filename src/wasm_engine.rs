@@ -1,12 +1,35 @@
-use anyhow::{Context, Result};
-use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, env, sync::RwLock};
-use wasmtime::{Engine, Store, Config as WasmtimeConfig};
-use wasmtime::component::{Component, Linker, ResourceTable, TypedFunc};
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::{Lazy, OnceCell};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env,
+    fmt,
+    fs,
+    future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    sync::RwLock,
+    thread,
+    time::{Duration, SystemTime},
+};
+use wasmtime_wasi::{DirPerms, FilePerms};
+use wasmtime::{
+    Config as WasmtimeConfig,
+    Engine,
+    InstanceAllocationStrategy,
+    PoolConcurrencyLimitError,
+    PoolingAllocationConfig,
+    ResourceLimiter,
+    Store,
+    Trap,
+    WasmBacktrace,
+    WasmBacktraceDetails,
+};
+use wasmtime::component::{Component, ComponentExportIndex, Linker, ResourceTable};
 use wasmtime_wasi::{
     add_to_linker_async as wasi_add,
-    pipe::{MemoryInputPipe, MemoryOutputPipe},
     WasiCtx,
     WasiCtxBuilder,
     WasiView,
@@ -14,137 +37,757 @@ use wasmtime_wasi::{
 use wasmtime_wasi_io::IoView;
 use wasmtime_wasi_http::{
     add_only_http_to_linker_async,
+    bindings::http::types::ErrorCode,
+    body::HyperOutgoingBody,
+    types::{default_send_request, HostFutureIncomingResponse, OutgoingRequestConfig},
+    HttpResult,
     WasiHttpCtx,
     WasiHttpView,
 };
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
-pub struct WasmOutput {
-    pub app_url: Option<String>,
-    #[serde(default)]
-    pub headers_to_update: HashMap<String, String>,
-    #[serde(default)]
-    pub headers_to_remove: Vec<String>,
-    #[serde(default)]
-    pub response_headers_to_add: HashMap<String, String>,
-    #[serde(default)]
-    pub response_headers_to_remove: Vec<String>,
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "request-hook",
+        async: true,
+    });
+}
+
+use bindings::{RequestHook, RequestHookPre};
+use bindings::rilot::proxy::logging::Level as GuestLevel;
+pub use bindings::rilot::proxy::types::{
+    Request as WasmRequest,
+    RequestOverride,
+    Response as WasmResponse,
+    ResponseOverride,
+    UpstreamResponse,
+};
+
+use crate::config::{Config, EngineConfig, ProxyConfig, SandboxConfig, WasmLimits};
+use crate::guest_log::GuestLog;
+use crate::kv;
+
+/// Route settings that shape how an override is instantiated.
+#[derive(Clone, Copy)]
+pub struct RunOptions<'a> {
+    pub limits: &'a WasmLimits,
+    pub sandbox: &'a SandboxConfig,
+    /// Route name and request ID attached to the guest's log lines.
+    pub app_name: &'a str,
+    pub request_id: &'a str,
+}
+
+impl<'a> RunOptions<'a> {
+    pub fn for_request(self, request_id: &'a str) -> Self {
+        RunOptions { request_id, ..self }
+    }
+}
+
+impl<'a> From<&'a ProxyConfig> for RunOptions<'a> {
+    fn from(route: &'a ProxyConfig) -> Self {
+        RunOptions { limits: &route.limits, sandbox: &route.sandbox, app_name: &route.app_name, request_id: "-" }
+    }
+}
+
+/// Options for the global `pre_routing` / `post_routing` overrides.
+impl<'a> From<&'a Config> for RunOptions<'a> {
+    fn from(cfg: &'a Config) -> Self {
+        RunOptions { limits: &cfg.global_limits, sandbox: &cfg.global_sandbox, app_name: "global", request_id: "-" }
+    }
+}
+
+/// Raised when an override runs past one of its route's `limits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Fuel,
+    Timeout,
+    Memory,
+    /// Filled its captured stdout or stderr.
+    Output,
+    /// Every slot of the pooling allocator was in use.
+    Concurrency,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::Fuel => write!(f, "Wasm override ran out of fuel"),
+            LimitExceeded::Timeout => write!(f, "Wasm override exceeded its time limit"),
+            LimitExceeded::Memory => write!(f, "Wasm override exceeded its memory limit"),
+            LimitExceeded::Output => write!(f, "Wasm override exceeded its output limit"),
+            LimitExceeded::Concurrency => write!(f, "Wasm instance pool is exhausted"),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Marks errors raised while reading, compiling, linking or instantiating a
+/// component, as opposed to errors raised by the guest code itself.
+#[derive(Debug)]
+struct LoadFailed;
+
+impl fmt::Display for LoadFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to load Wasm override")
+    }
+}
+
+/// Why an override failed, for error accounting and `on_override_error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideErrorKind {
+    Trap,
+    /// Ran past `timeout_ms` or out of fuel.
+    Timeout,
+    /// Returned something rilot could not use.
+    BadOutput,
+    LoadFailure,
+    /// No pooling allocator slot was free.
+    Overloaded,
+}
+
+static OVERRIDE_ERRORS: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+
+impl OverrideErrorKind {
+    /// Classifies an error returned by [`run_modify_request`] or [`run_modify_response`].
+    pub fn of(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<LimitExceeded>() {
+            Some(LimitExceeded::Fuel | LimitExceeded::Timeout) => return OverrideErrorKind::Timeout,
+            Some(LimitExceeded::Memory) => return OverrideErrorKind::Trap,
+            Some(LimitExceeded::Output) => return OverrideErrorKind::BadOutput,
+            Some(LimitExceeded::Concurrency) => return OverrideErrorKind::Overloaded,
+            None => {}
+        }
+        if err.downcast_ref::<Trap>().is_some() {
+            OverrideErrorKind::Trap
+        } else if err.downcast_ref::<LoadFailed>().is_some() {
+            OverrideErrorKind::LoadFailure
+        } else {
+            // Anything else surfaced while lifting the guest's return value.
+            OverrideErrorKind::BadOutput
+        }
+    }
+
+    /// Counts one error of this kind and returns the new total.
+    pub fn record(self) -> u64 {
+        OVERRIDE_ERRORS[self as usize].fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Diagnostics pulled out of a failed override's error chain.
+#[derive(Debug, Default)]
+pub struct FailureDetails {
+    /// The error chain without the backtrace, joined with `: `.
+    pub message: String,
+    /// wasmtime's trap code, e.g. `UnreachableCodeReached`.
+    pub trap_code: Option<String>,
+    /// The guest stack at the trap, one frame per line.
+    pub backtrace: Option<String>,
+}
+
+impl FailureDetails {
+    pub fn of(err: &anyhow::Error) -> Self {
+        // wasmtime attaches the backtrace as a context layer of the chain.
+        let backtrace = err.downcast_ref::<WasmBacktrace>().map(|bt| bt.to_string());
+        FailureDetails {
+            message: err
+                .chain()
+                .map(ToString::to_string)
+                .filter(|cause| Some(cause) != backtrace.as_ref())
+                .collect::<Vec<_>>()
+                .join(": "),
+            trap_code: err.downcast_ref::<Trap>().map(|trap| format!("{:?}", trap)),
+            backtrace: backtrace.filter(|bt| !bt.trim().is_empty()),
+        }
+    }
+}
+
+impl fmt::Display for OverrideErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverrideErrorKind::Trap => write!(f, "trap"),
+            OverrideErrorKind::Timeout => write!(f, "timeout"),
+            OverrideErrorKind::BadOutput => write!(f, "bad_output"),
+            OverrideErrorKind::LoadFailure => write!(f, "load_failure"),
+            OverrideErrorKind::Overloaded => write!(f, "overloaded"),
+        }
+    }
+}
+
+/// Caps the size of each linear memory. Growth past the cap traps the guest
+/// with [`LimitExceeded::Memory`] instead of letting `memory.grow` fail.
+struct MemoryLimiter {
+    max_memory_bytes: Option<usize>,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        match self.max_memory_bytes {
+            Some(max) if desired > max => Err(LimitExceeded::Memory.into()),
+            _ => Ok(true),
+        }
+    }
+
+    fn table_growing(&mut self, _current: usize, _desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        Ok(true)
+    }
 }
 
 struct Host {
     table: ResourceTable,
     wasi: WasiCtx,
     http: WasiHttpCtx,
+    limiter: MemoryLimiter,
+    allowed_hosts: Option<Vec<String>>,
+    log: GuestLog,
 }
 
 impl IoView for Host { fn table(&mut self) -> &mut ResourceTable { &mut self.table } }
 impl WasiView for Host { fn ctx(&mut self) -> &mut WasiCtx { &mut self.wasi } }
-impl WasiHttpView for Host { fn ctx(&mut self) -> &mut WasiHttpCtx { &mut self.http } }
+impl WasiHttpView for Host {
+    fn ctx(&mut self) -> &mut WasiHttpCtx { &mut self.http }
 
-static ENGINE: Lazy<Engine> = Lazy::new(|| {
-    Engine::new(&WasmtimeConfig::new().async_support(true).wasm_component_model(true)).unwrap()
+    fn send_request(
+        &mut self,
+        request: http::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        if let Some(allowed) = &self.allowed_hosts {
+            let authority = request.uri().authority();
+            let permitted = authority.is_some_and(|a| {
+                allowed.iter().any(|pattern| host_matches(pattern, a.host(), a.port_u16()))
+            });
+            if !permitted {
+                log::warn!("🚫 Blocked outbound request from Wasm to: {}", request.uri());
+                return Err(ErrorCode::HttpRequestDenied.into());
+            }
+        }
+        Ok(default_send_request(request, config))
+    }
+}
+
+/// Matches `host`, `host:port` or `*.domain` allowlist entries.
+fn host_matches(pattern: &str, host: &str, port: Option<u16>) -> bool {
+    let (pattern_host, pattern_port) = match pattern.rsplit_once(':') {
+        Some((h, p)) => match p.parse::<u16>() {
+            Ok(p) => (h, Some(p)),
+            Err(_) => (pattern, None),
+        },
+        None => (pattern, None),
+    };
+    if pattern_port.is_some() && pattern_port != port {
+        return false;
+    }
+    match pattern_host.strip_prefix("*.") {
+        Some(domain) => host
+            .to_ascii_lowercase()
+            .strip_suffix(&domain.to_ascii_lowercase())
+            .is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+        None => pattern_host.eq_ignore_ascii_case(host),
+    }
+}
+impl bindings::rilot::proxy::types::Host for Host {}
+
+impl bindings::rilot::proxy::logging::Host for Host {
+    async fn log(&mut self, level: GuestLevel, message: String) {
+        let level = match level {
+            GuestLevel::Trace => log::Level::Trace,
+            GuestLevel::Debug => log::Level::Debug,
+            GuestLevel::Info => log::Level::Info,
+            GuestLevel::Warn => log::Level::Warn,
+            GuestLevel::Error => log::Level::Error,
+        };
+        self.log.log(level, &message);
+    }
+}
+
+impl bindings::rilot::proxy::keyvalue::Host for Host {
+    async fn get(&mut self, key: String) -> Option<Vec<u8>> {
+        kv::store().get(&key)
+    }
+
+    async fn set(&mut self, key: String, value: Vec<u8>, ttl_ms: Option<u64>) -> Result<(), String> {
+        kv::store().set(key, value, ttl_ms)
+    }
+
+    async fn delete(&mut self, key: String) -> bool {
+        kv::store().delete(&key)
+    }
+
+    async fn increment(&mut self, key: String, delta: i64, ttl_ms: Option<u64>) -> Result<i64, String> {
+        kv::store().increment(key, delta, ttl_ms)
+    }
+}
+
+/// How often the background ticker advances the engine epoch.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+static ENGINE: OnceCell<Engine> = OnceCell::new();
+
+static LINKER: Lazy<Linker<Host>> = Lazy::new(|| {
+    let mut linker = Linker::new(engine());
+    wasi_add(&mut linker).expect("Failed to link WASI interfaces");
+    add_only_http_to_linker_async(&mut linker).expect("Failed to link wasi-http interfaces");
+    RequestHook::add_to_linker(&mut linker, |host: &mut Host| host).expect("Failed to link rilot interfaces");
+    log::debug!("🔗 WASI and rilot interfaces linked.");
+    linker
 });
 
-static COMPONENT_CACHE: Lazy<RwLock<HashMap<String, Component>>> =
+/// A compiled component, pre-linked and type-checked against `request-hook`.
+#[derive(Clone)]
+struct LoadedComponent {
+    pre: RequestHookPre<Host>,
+    modify_response: Option<ComponentExportIndex>,
+}
+
+/// Identifies one version of a component file on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+struct CacheEntry {
+    stamp: FileStamp,
+    loaded: LoadedComponent,
+}
+
+static COMPONENT_CACHE: Lazy<RwLock<HashMap<String, CacheEntry>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
-pub async fn run_modify_request(component_path: &str, input_json: &str) -> Result<WasmOutput> {
-    let component = {
-        let is_production = env::var("RILOT_ENV")
-            .map(|val| val.eq_ignore_ascii_case("production"))
-            .unwrap_or(false);
-
-        if is_production {
-            log::debug!("📦 [Prod Mode] Checking cache for component: {}", component_path);
-            let read_cache = COMPONENT_CACHE.read().expect("Cache lock poisoned");
-            if let Some(comp) = read_cache.get(component_path) {
-                log::info!("📦 [Prod Mode] Found component in cache: {}", component_path);
-                comp.clone()
-            } else {
-                drop(read_cache);
-                log::info!("📦 [Prod Mode] Compiling and caching component: {}", component_path);
-                let comp = Component::from_file(&*ENGINE, component_path)
-                    .with_context(|| format!("Failed to load Wasm component file: {}", component_path))?;
-                let mut write_cache = COMPONENT_CACHE.write().expect("Cache lock poisoned");
-                write_cache.insert(component_path.to_string(), comp.clone());
-                log::info!("✅ [Prod Mode] Component cached: {}", component_path);
-                comp
-            }
+/// Set once the background watcher owns invalidation, so lookups can skip the `stat`.
+static WATCHER_RUNNING: AtomicBool = AtomicBool::new(false);
+/// `RILOT_ENV=production`: cached components are trusted as-is, so lookups skip
+/// the `stat` and only the watcher, if enabled, picks up changed files.
+static PRODUCTION: Lazy<bool> =
+    Lazy::new(|| env::var("RILOT_ENV").is_ok_and(|v| v.eq_ignore_ascii_case("production")));
+static RELOAD_COUNT: AtomicU64 = AtomicU64::new(0);
+/// `engine.load_precompiled`: whether `.cwasm` artifacts are used at all.
+static LOAD_PRECOMPILED: AtomicBool = AtomicBool::new(false);
+
+/// Builds the shared engine from `config.engine`. Must run before the first
+/// request; otherwise the engine falls back to the default settings.
+pub fn init(cfg: &EngineConfig) -> Result<()> {
+    let engine = build_engine(cfg)?;
+    LOAD_PRECOMPILED.store(cfg.load_precompiled, Ordering::Relaxed);
+    if *PRODUCTION {
+        log::info!("🏭 RILOT_ENV=production: cached components are not re-checked on each request");
+    }
+    ENGINE.set(engine).map_err(|_| anyhow!("Wasm engine already initialized"))
+}
+
+fn engine() -> &'static Engine {
+    ENGINE.get_or_init(|| build_engine(&EngineConfig::default()).expect("Failed to create Wasm engine"))
+}
+
+fn build_engine(cfg: &EngineConfig) -> Result<Engine> {
+    let mut wasm_config = WasmtimeConfig::new();
+    wasm_config
+        .async_support(true)
+        .wasm_component_model(true)
+        .consume_fuel(true)
+        .epoch_interruption(true)
+        .wasm_backtrace(true)
+        .wasm_backtrace_details(if cfg.backtrace_details {
+            WasmBacktraceDetails::Enable
         } else {
-            log::debug!("📦 [Dev Mode] Compiling component (no cache): {}", component_path);
-            Component::from_file(&*ENGINE, component_path)
-                .with_context(|| format!("Failed to load Wasm component file: {}", component_path))?
-        }
-    };
-    log::debug!("✅ Component loaded/retrieved.");
+            WasmBacktraceDetails::Disable
+        });
 
-    log::debug!("🔧 Creating I/O pipes...");
-    let input_json_owned = input_json.to_string();
-    let stdin_pipe = MemoryInputPipe::new(input_json_owned);
-    let stdout_pipe = MemoryOutputPipe::new(4096);
+    if let Some(pool) = &cfg.pooling {
+        log::info!("🏊 Using pooling allocator: {:?}", pool);
+        let mut pooling = PoolingAllocationConfig::default();
+        pooling
+            .total_component_instances(pool.total_component_instances)
+            .total_core_instances(pool.total_core_instances)
+            .total_memories(pool.total_memories)
+            .total_tables(pool.total_tables)
+            .total_stacks(pool.total_stacks)
+            .max_memory_size(pool.max_memory_size);
+        wasm_config.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+    }
 
-    log::debug!("🔧 Building WASI context with pipes...");
-    let mut builder = WasiCtxBuilder::new();
-    builder
-        .inherit_args()
-        .inherit_env()
-        .stdin(stdin_pipe.clone())
-        .stdout(stdout_pipe.clone())
-        .inherit_stderr();
+    let engine = Engine::new(&wasm_config).context("Failed to create Wasm engine")?;
 
-    let wasi_ctx = builder.build();
-    let host = Host {
-        table: ResourceTable::default(),
-        wasi: wasi_ctx,
-        http: WasiHttpCtx::new(),
-    };
-    let mut store = Store::new(&*ENGINE, host);
-    log::debug!("🔧 Host and Store created.");
+    let ticker = engine.weak();
+    thread::spawn(move || {
+        while let Some(engine) = ticker.upgrade() {
+            engine.increment_epoch();
+            drop(engine);
+            thread::sleep(EPOCH_TICK);
+        }
+    });
+
+    Ok(engine)
+}
+
+const MODIFY_RESPONSE_EXPORT: &str = "modify-response";
 
-    let mut linker = Linker::new(&*ENGINE);
+pub async fn run_modify_request(component_path: &str, opts: RunOptions<'_>, request: &WasmRequest) -> Result<RequestOverride> {
+    with_limits(opts.limits, modify_request(component_path, opts, request)).await
+}
 
-    wasi_add(&mut linker)?;
-    add_only_http_to_linker_async(&mut linker)?;
-    log::debug!("🔗 WASI interfaces linked.");
+async fn modify_request(component_path: &str, opts: RunOptions<'_>, request: &WasmRequest) -> Result<RequestOverride> {
+    let loaded = load_component(component_path).context(LoadFailed)?;
+    let mut store = new_store(component_path, opts).context(LoadFailed)?;
 
     log::debug!("🚀 Instantiating component...");
-    let instance = linker.instantiate_async(&mut store, &component).await?;
+    let hook = loaded.pre.instantiate_async(&mut store).await.context(LoadFailed)?;
     log::debug!("✅ Component instantiated.");
 
-    let actual_export_name = "modify-request";
+    log::debug!("Calling `modify-request` in Wasm...");
+    let output = hook
+        .call_modify_request(&mut store, request)
+        .await
+        .context("Failed during Wasm function call 'modify-request'")
+        .map_err(|e| tag_output_overflow(&store, e))?;
+    log::debug!("✨ `modify-request` returned: {:?}", output);
 
-    log::debug!("🔍 Finding function export named '{}'...", actual_export_name);
+    Ok(output)
+}
 
-    let modify_request_func: TypedFunc<(), ()> = instance
-        .get_typed_func(&mut store, actual_export_name)
-        .with_context(|| format!("Failed to find expected function export '{}'", actual_export_name))?;
-    log::debug!("✅ Found `{}` function export.", actual_export_name);
+/// Runs the optional `modify-response` export. Returns `Ok(None)` when the
+/// component does not export it, so request-only overrides keep working.
+pub async fn run_modify_response(component_path: &str, opts: RunOptions<'_>, response: &UpstreamResponse) -> Result<Option<ResponseOverride>> {
+    with_limits(opts.limits, modify_response(component_path, opts, response)).await
+}
 
+async fn modify_response(component_path: &str, opts: RunOptions<'_>, response: &UpstreamResponse) -> Result<Option<ResponseOverride>> {
+    let loaded = load_component(component_path).context(LoadFailed)?;
+    let Some(export) = loaded.modify_response else {
+        log::debug!("⏭️ Component has no `{}` export, leaving response untouched.", MODIFY_RESPONSE_EXPORT);
+        return Ok(None);
+    };
+    let mut store = new_store(component_path, opts).context(LoadFailed)?;
 
-    log::debug!("Calling `{}` in Wasm (I/O via stdio pipes)...", actual_export_name);
-    modify_request_func
-        .call_async(&mut store, ())
+    log::debug!("🚀 Instantiating component...");
+    let instance = loaded.pre.instance_pre().instantiate_async(&mut store).await.context(LoadFailed)?;
+    log::debug!("✅ Component instantiated.");
+
+    let modify_response_func = instance
+        .get_typed_func::<(&UpstreamResponse,), (ResponseOverride,)>(&mut store, &export)
+        .with_context(|| format!("Export '{}' does not match the `rilot-override` world", MODIFY_RESPONSE_EXPORT))
+        .context(LoadFailed)?;
+
+    log::debug!("Calling `{}` in Wasm...", MODIFY_RESPONSE_EXPORT);
+    let (output,) = modify_response_func
+        .call_async(&mut store, (response,))
         .await
-        .with_context(|| format!("Failed during Wasm function call '{}'", actual_export_name))?
-        ;
-    log::debug!("✅ `{}` returned.", actual_export_name);
+        .with_context(|| format!("Failed during Wasm function call '{}'", MODIFY_RESPONSE_EXPORT))
+        .map_err(|e| tag_output_overflow(&store, e))?;
+    modify_response_func.post_return_async(&mut store).await?;
+    log::debug!("✨ `{}` returned: {:?}", MODIFY_RESPONSE_EXPORT, output);
+
+    Ok(Some(output))
+}
 
-    drop(store);
-    let output_bytes = stdout_pipe.contents();
-    let output_json_string = String::from_utf8(output_bytes.to_vec())
-        .context("Failed to decode Wasm stdout as UTF-8")?;
+/// A guest fails on the first write after its stdout or stderr fills up,
+/// usually with a panic of its own. Name the real cause instead.
+fn tag_output_overflow(store: &Store<Host>, err: anyhow::Error) -> anyhow::Error {
+    if store.data().log.overflowed() {
+        err.context(LimitExceeded::Output)
+    } else {
+        err
+    }
+}
 
-    log::debug!("📄 Read output JSON string from stdout pipe ({} bytes)", output_json_string.len());
-    log::trace!("Raw stdout: {}", output_json_string);
+/// Enforces `timeout_ms` as a wall-clock deadline (covering time spent in
+/// host calls such as outbound HTTP) and tags limit traps and pool
+/// exhaustion with [`LimitExceeded`].
+async fn with_limits<T>(limits: &WasmLimits, fut: impl Future<Output = Result<T>>) -> Result<T> {
+    let result = match limits.timeout_ms {
+        Some(ms) => match tokio::time::timeout(Duration::from_millis(ms), fut).await {
+            Ok(result) => result,
+            Err(_) => return Err(LimitExceeded::Timeout.into()),
+        },
+        None => fut.await,
+    };
 
+    result.map_err(|e| match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => e.context(LimitExceeded::Fuel),
+        Some(Trap::Interrupt) => e.context(LimitExceeded::Timeout),
+        _ if e.downcast_ref::<PoolConcurrencyLimitError>().is_some() => e.context(LimitExceeded::Concurrency),
+        _ => e,
+    })
+}
 
-    let output: WasmOutput = if output_json_string.trim().is_empty() {
-        log::warn!("⚠️ Wasm component wrote empty string to stdout, returning default output.");
-        WasmOutput::default()
+/// Returns the cached component for `component_path`, recompiling when the
+/// file's mtime or size changed since it was cached. In production mode a
+/// cached component is returned without checking the file.
+fn load_component(component_path: &str) -> Result<LoadedComponent> {
+    let current = if WATCHER_RUNNING.load(Ordering::Relaxed) || *PRODUCTION {
+        None
     } else {
-        serde_json::from_str(&output_json_string).with_context(|| format!("Failed to parse JSON output from Wasm stdout: '{}'", output_json_string))?
+        Some(file_stamp(component_path)?)
     };
-    log::debug!("✨ Deserialized WasmOutput: {:?}", output);
 
-    Ok(output)
+    {
+        let read_cache = COMPONENT_CACHE.read().expect("Cache lock poisoned");
+        if let Some(entry) = read_cache.get(component_path) {
+            if current.is_none_or(|stamp| stamp == entry.stamp) {
+                log::debug!("📦 Found component in cache: {}", component_path);
+                return Ok(entry.loaded.clone());
+            }
+        }
+    }
+
+    let stamp = match current {
+        Some(stamp) => stamp,
+        None => file_stamp(component_path)?,
+    };
+    log::info!("📦 Loading and caching component: {}", component_path);
+    let loaded = compile_component(component_path)?;
+    store_component(component_path, stamp, loaded.clone());
+    Ok(loaded)
+}
+
+fn store_component(component_path: &str, stamp: FileStamp, loaded: LoadedComponent) {
+    let mut write_cache = COMPONENT_CACHE.write().expect("Cache lock poisoned");
+    let previous = write_cache.insert(component_path.to_string(), CacheEntry { stamp, loaded });
+    drop(write_cache);
+
+    if previous.is_some() {
+        let reloads = RELOAD_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+        log::info!("🔄 Reloaded component: {} (reloads_total={})", component_path, reloads);
+    } else {
+        log::info!("✅ Component cached: {}", component_path);
+    }
+}
+
+fn file_stamp(component_path: &str) -> Result<FileStamp> {
+    let metadata = fs::metadata(component_path)
+        .with_context(|| format!("Failed to read Wasm component file: {}", component_path))?;
+    Ok(FileStamp { modified: metadata.modified()?, len: metadata.len() })
+}
+
+/// Polls every cached component file and swaps in a recompiled version when
+/// it changes. Requests keep using the previous version until the swap, and
+/// after a failed recompile.
+pub fn spawn_watcher(interval: Duration) {
+    WATCHER_RUNNING.store(true, Ordering::Relaxed);
+    log::info!("👀 Watching Wasm components for changes every {:?}", interval);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let tracked: Vec<(String, FileStamp)> = COMPONENT_CACHE
+                .read()
+                .expect("Cache lock poisoned")
+                .iter()
+                .map(|(path, entry)| (path.clone(), entry.stamp))
+                .collect();
+
+            for (path, old_stamp) in tracked {
+                let stamp = match file_stamp(&path) {
+                    Ok(stamp) if stamp != old_stamp => stamp,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        log::warn!("⚠️ Cannot check component for changes: {:#}", e);
+                        continue;
+                    }
+                };
+
+                log::info!("🔁 Component changed on disk, recompiling: {}", path);
+                let compile_path = path.clone();
+                let compiled = match tokio::task::spawn_blocking(move || compile_component(&compile_path)).await {
+                    Ok(Ok(loaded)) => check_exports(&path, &loaded).await.map(|_| loaded),
+                    Ok(Err(e)) => Err(e),
+                    Err(e) => {
+                        log::error!("❌ Component reload task failed: {}", e);
+                        continue;
+                    }
+                };
+                match compiled {
+                    Ok(loaded) => store_component(&path, stamp, loaded),
+                    Err(e) => {
+                        log::error!("❌ Failed to reload component, keeping previous version: {:#}", e);
+                        // Remember the broken version so it is not recompiled on every tick.
+                        if let Some(entry) = COMPONENT_CACHE.write().expect("Cache lock poisoned").get_mut(&path) {
+                            entry.stamp = stamp;
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn compile_component(component_path: &str) -> Result<LoadedComponent> {
+    let component = read_component(component_path)?;
+    let instance_pre = LINKER
+        .instantiate_pre(&component)
+        .with_context(|| format!("Failed to link Wasm component: {}", component_path))?;
+    let pre = RequestHookPre::new(instance_pre)
+        .with_context(|| format!("Component does not implement the `request-hook` world: {}", component_path))?;
+    let modify_response = component
+        .export_index(None, MODIFY_RESPONSE_EXPORT)
+        .map(|(_, index)| index);
+    Ok(LoadedComponent { pre, modify_response })
+}
+
+/// With `engine.load_precompiled`, loads the `.cwasm` artifact written by
+/// `precompile` for exactly these `.wasm` bytes and this engine; otherwise
+/// compiles the `.wasm` with Cranelift.
+fn read_component(component_path: &str) -> Result<Component> {
+    if !LOAD_PRECOMPILED.load(Ordering::Relaxed) {
+        return Component::from_file(engine(), component_path)
+            .with_context(|| format!("Failed to load Wasm component file: {}", component_path));
+    }
+
+    let wasm = fs::read(component_path)
+        .with_context(|| format!("Failed to read Wasm component file: {}", component_path))?;
+    let artifact = artifact_path(component_path, &wasm);
+    if artifact.is_file() {
+        // SAFETY: loading artifacts is opt-in, and the name embeds a hash of both
+        // the source bytes and this engine's settings, so a matching file was
+        // written by `precompile` for this exact component.
+        match unsafe { Component::deserialize_file(engine(), &artifact) } {
+            Ok(component) => {
+                log::info!("⚡ Loaded precompiled component: {}", artifact.display());
+                return Ok(component);
+            }
+            Err(e) => log::warn!("⚠️ Ignoring unusable artifact {}: {:#}", artifact.display(), e),
+        }
+    } else {
+        log::debug!("📦 No precompiled artifact at {}", artifact.display());
+    }
+
+    Component::new(engine(), &wasm)
+        .with_context(|| format!("Failed to load Wasm component file: {}", component_path))
+}
+
+/// Ahead-of-time compiles `component_path` and writes the artifact next to it.
+pub fn precompile(component_path: &str) -> Result<PathBuf> {
+    let wasm = fs::read(component_path)
+        .with_context(|| format!("Failed to read Wasm component file: {}", component_path))?;
+    let compiled = engine()
+        .precompile_component(&wasm)
+        .with_context(|| format!("Failed to compile Wasm component: {}", component_path))?;
+
+    let artifact = artifact_path(component_path, &wasm);
+    let tmp = artifact.with_extension("cwasm.tmp");
+    fs::write(&tmp, compiled).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &artifact).with_context(|| format!("Failed to write {}", artifact.display()))?;
+    Ok(artifact)
+}
+
+/// `foo.wasm` -> `foo.<hash>.cwasm`, hashing the component bytes together with
+/// the engine's compatibility hash, so an edited `.wasm` or another wasmtime
+/// version or engine configuration never picks up a stale artifact.
+fn artifact_path(component_path: &str, wasm: &[u8]) -> PathBuf {
+    let mut engine_hasher = DefaultHasher::new();
+    engine().precompile_compatibility_hash().hash(&mut engine_hasher);
+    let digest = Sha256::new()
+        .chain_update(engine_hasher.finish().to_le_bytes())
+        .chain_update(wasm)
+        .finalize();
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    Path::new(component_path).with_extension(format!("{}.cwasm", hex))
+}
+
+/// Loads `component_path` into the cache and checks that its exports match
+/// the `rilot:proxy` worlds, so broken overrides fail at startup rather than
+/// on the first matching request.
+pub async fn validate(component_path: &str) -> Result<()> {
+    let loaded = load_component(component_path)?;
+    check_exports(component_path, &loaded).await
+}
+
+/// Instantiates the component once and type-checks `modify-request` and, when
+/// exported, `modify-response`. Export types are only checked on instantiation.
+async fn check_exports(component_path: &str, loaded: &LoadedComponent) -> Result<()> {
+    let limits = WasmLimits::default();
+    let sandbox = SandboxConfig::default();
+    let opts = RunOptions { limits: &limits, sandbox: &sandbox, app_name: "validate", request_id: "-" };
+    let mut store = new_store(component_path, opts)?;
+
+    let instance = loaded.pre.instance_pre()
+        .instantiate_async(&mut store)
+        .await
+        .context("Failed to instantiate component")?;
+    RequestHook::new(&mut store, &instance)
+        .context("`modify-request` does not match the `request-hook` world")?;
+    if let Some(export) = &loaded.modify_response {
+        instance
+            .get_typed_func::<(&UpstreamResponse,), (ResponseOverride,)>(&mut store, export)
+            .with_context(|| format!("`{}` does not match the `rilot-override` world", MODIFY_RESPONSE_EXPORT))?;
+    }
+    Ok(())
+}
+
+fn new_store(component_path: &str, opts: RunOptions<'_>) -> Result<Store<Host>> {
+    let limits = opts.limits;
+
+    log::debug!("🔧 Building sandboxed WASI context...");
+    let guest_log = GuestLog::new(opts.app_name, component_path, opts.request_id, limits.max_output_bytes);
+    let (stdout, stderr) = guest_log.pipes();
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .stdout(stdout)
+        .stderr(stderr);
+
+    for name in &opts.sandbox.env_allowlist {
+        if let Ok(value) = env::var(name) {
+            builder.env(name, value);
+        }
+    }
+    for (name, value) in &opts.sandbox.env {
+        builder.env(name, value);
+    }
+    for dir in &opts.sandbox.preopens {
+        builder
+            .preopened_dir(&dir.host_path, &dir.guest_path, DirPerms::READ, FilePerms::READ)
+            .with_context(|| format!("Failed to preopen directory for Wasm: {}", dir.host_path))?;
+    }
+
+    let wasi_ctx = builder.build();
+    let host = Host {
+        table: ResourceTable::default(),
+        wasi: wasi_ctx,
+        http: WasiHttpCtx::new(),
+        limiter: MemoryLimiter { max_memory_bytes: limits.max_memory_bytes },
+        allowed_hosts: opts.sandbox.allowed_hosts.clone(),
+        log: guest_log,
+    };
+    let mut store = Store::new(engine(), host);
+    store.limiter(|host| &mut host.limiter);
+    store.set_fuel(limits.fuel.unwrap_or(u64::MAX))?;
+    let deadline_ticks = limits.timeout_ms
+        .map(|ms| ms.div_ceil(EPOCH_TICK.as_millis() as u64))
+        .unwrap_or(u64::MAX / 2);
+    store.set_epoch_deadline(deadline_ticks.max(1));
+    log::debug!("🔧 Host and Store created.");
+
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_matches_allowlist_entries() {
+        assert!(host_matches("api.example.com", "api.example.com", Some(443)));
+        assert!(!host_matches("api.example.com", "example.com", Some(443)));
+
+        assert!(host_matches("api.example.com:8443", "api.example.com", Some(8443)));
+        assert!(!host_matches("api.example.com:8443", "api.example.com", Some(443)));
+        assert!(!host_matches("api.example.com:8443", "api.example.com", None));
+
+        assert!(host_matches("*.example.com", "a.example.com", None));
+        assert!(host_matches("*.example.com", "a.b.example.com", None));
+        assert!(!host_matches("*.example.com", "badexample.com", None));
+        assert!(host_matches("*.example.com:443", "a.example.com", Some(443)));
+        assert!(!host_matches("*.example.com:443", "a.example.com", Some(80)));
+
+        // A wildcard covers subdomains only, not the bare domain.
+        assert!(!host_matches("*.example.com", "example.com", None));
+        assert!(!host_matches("*.example.com", ".example.com", None));
+
+        assert!(host_matches("API.Example.com", "api.EXAMPLE.com", None));
+        assert!(host_matches("*.Example.COM", "A.example.com", None));
+        assert!(host_matches("*.example.com", "A.EXAMPLE.COM", None));
+    }
 }